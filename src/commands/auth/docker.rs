@@ -3,32 +3,47 @@ use std::process::Stdio;
 use anyhow::{bail, Result};
 use clap::Parser;
 use tokio::io::AsyncWriteExt;
-use tokio::process::Command;
 
 use crate::state::State;
-use crate::utils::in_path;
+use crate::store::Store;
+use crate::utils::engine::ContainerEngine;
 
 #[derive(Debug, Parser)]
-#[clap(about = "Authenticate the current user with Docker")]
+#[clap(about = "Authenticate the current user with Docker, Podman or nerdctl")]
 #[group(skip)]
-pub struct Options {}
+pub struct Options {
+    #[clap(
+        long,
+        help = "Container engine to use for local builds from now on, defaults to the first one installed"
+    )]
+    pub engine: Option<ContainerEngine>,
+}
+
+pub async fn handle(options: &Options, state: &mut State) -> Result<()> {
+    if let Some(engine) = options.engine {
+        state.ctx.container_engine = Some(engine);
+        state.ctx.save().await?;
 
-pub async fn handle(_options: &Options, state: &mut State) -> Result<()> {
-    if !in_path("docker").await {
-        bail!("Docker is not installed");
+        log::info!("Using `{engine}` for local builds");
     }
 
+    let engine = ContainerEngine::from_context(&state.ctx).await?;
+
     state.login(None).await?;
 
     let current = state.ctx.current.take().unwrap();
 
     login(
+        engine,
         &current.email,
         state.auth.authorized.get(&current.id).unwrap(),
     )
     .await?;
 
-    log::info!("Successfully logged in as `{}` with Docker", current.email);
+    log::info!(
+        "Successfully logged in as `{}` with {engine}",
+        current.email
+    );
 
     Ok(())
 }
@@ -36,10 +51,17 @@ pub async fn handle(_options: &Options, state: &mut State) -> Result<()> {
 pub const HOP_REGISTRY_URL: &str = "registry.hop.io";
 
 // This login is separated into two commands.
-pub async fn login(username: &str, password: &str) -> Result<()> {
+pub async fn login(engine: ContainerEngine, username: &str, password: &str) -> Result<()> {
     // First we need to know if we are already logged in to the registry
-    let status = Command::new("docker")
-        .arg("login")
+    let mut command = engine.command();
+    command.arg("login");
+
+    // podman would prompt for credentials instead of reusing the stored ones
+    if engine == ContainerEngine::Podman {
+        command.arg("--get-login");
+    }
+
+    let status = command
         .arg(HOP_REGISTRY_URL)
         // making the stdin piped disables tty
         .stdin(Stdio::piped())
@@ -47,22 +69,23 @@ pub async fn login(username: &str, password: &str) -> Result<()> {
         .stderr(Stdio::null())
         .status().await?;
 
-    log::debug!("Docker login exited with {status}");
+    log::debug!("{engine} login exited with {status}");
 
     // if the exit code is 0 we are already logged in
     if status.success() {
-        log::debug!("Docker login successful");
+        log::debug!("{engine} login successful");
 
         return Ok(());
     }
 
-    login_new(username, password).await
+    login_new(engine, username, password).await
 }
 
-pub async fn login_new(username: &str, password: &str) -> Result<()> {
+pub async fn login_new(engine: ContainerEngine, username: &str, password: &str) -> Result<()> {
     // if we are not logged in we need to login using the email and token (pat or
     // bearer, ptk)
-    let mut child = Command::new("docker")
+    let mut child = engine
+        .command()
         .arg("login")
         .arg("--username")
         .arg(username)
@@ -85,11 +108,11 @@ pub async fn login_new(username: &str, password: &str) -> Result<()> {
 
     let status = child.wait().await?;
 
-    log::debug!("Docker login exited with {status}");
+    log::debug!("{engine} login exited with {status}");
 
     // if the exit code is 0 we are already logged in
     if status.success() {
-        log::debug!("Docker login successful");
+        log::debug!("{engine} login successful");
 
         return Ok(());
     }
@@ -98,5 +121,9 @@ pub async fn login_new(username: &str, password: &str) -> Result<()> {
     // 1. docker daemon is not running
     // 2. registry authentication layer is down
     // 3. the users credentials just expired
-    bail!("Docker login failed, is the docker daemon running?");
+    if engine == ContainerEngine::Podman {
+        bail!("{engine} login failed");
+    }
+
+    bail!("{engine} login failed, is the {engine} daemon running?");
 }
@@ -10,7 +10,7 @@ use self::browser_auth::browser_login;
 use self::flags_auth::flags_login;
 use crate::state::State;
 use crate::store::Store;
-use crate::utils::in_path;
+use crate::utils::engine::ContainerEngine;
 
 const WEB_AUTH_URL: &str = "https://console.hop.io/auth/callback/cli";
 const PAT_FALLBACK_URL: &str = "https://console.hop.io/settings/pats";
@@ -73,14 +73,20 @@ pub async fn token(token: &str, mut state: State) -> Result<()> {
         .insert(authorized.id.clone(), token.to_string());
     state.auth.save().await?;
 
-    if !state.is_ci
-        && in_path("docker").await
-        && dialoguer::Confirm::new()
-            .with_prompt("Docker was detected, would you like to login to the Hop registry?")
+    if state.is_ci {
+        return Ok(());
+    }
+
+    if let Ok(engine) = ContainerEngine::from_context(&state.ctx).await {
+        if dialoguer::Confirm::new()
+            .with_prompt(format!(
+                "{engine} was detected, would you like to login to the Hop registry?"
+            ))
             .default(false)
             .interact()?
-    {
-        super::docker::login_new(&authorized.email, token).await?;
+        {
+            super::docker::login_new(engine, &authorized.email, token).await?;
+        }
     }
 
     Ok(())
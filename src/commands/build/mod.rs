@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+use std::env::current_dir;
+use std::path::PathBuf;
+
+use anyhow::{ensure, Context, Result};
+use clap::Parser;
+
+use crate::commands::deploy::local::types::RunOptions;
+use crate::commands::deploy::local::util::parse_platform;
+use crate::commands::deploy::local::{build_image, run_image};
+use crate::commands::gateways::util::get_all_gateways;
+use crate::commands::ignite::types::Deployment;
+use crate::commands::ignite::utils::get_deployment;
+use crate::commands::secrets::utils::get_secret_name;
+use crate::state::State;
+use crate::store::hopfile::HopFile;
+use crate::utils::engine::ContainerEngine;
+
+#[derive(Debug, Parser)]
+#[clap(about = "Build an image locally the same way the Hop builder does, without pushing it")]
+#[group(skip)]
+pub struct Options {
+    #[clap(
+        name = "dir",
+        help = "Directory to build, defaults to current directory"
+    )]
+    path: Option<PathBuf>,
+
+    #[clap(
+        short,
+        long,
+        help = "Tag of the image, defaults to the image of the linked deployment"
+    )]
+    tag: Option<String>,
+
+    #[clap(
+        short,
+        long,
+        help = "Deployment to take the env and ports from, defaults to the linked one"
+    )]
+    deployment: Option<String>,
+
+    #[clap(
+        long,
+        help = "Environment from the hopfile to take the deployment from"
+    )]
+    environment: Option<String>,
+
+    #[clap(
+        long = "platform",
+        help = "Platform to build for, can be repeated to build a multi-platform image with buildx",
+        value_parser = parse_platform
+    )]
+    platforms: Vec<String>,
+
+    #[clap(
+        short,
+        long,
+        help = "Run the image after building it with the env and ports of the deployment"
+    )]
+    run: bool,
+}
+
+pub async fn handle(options: Options, mut state: State) -> Result<()> {
+    let mut dir = current_dir().context("Could not get current directory")?;
+
+    if let Some(path) = options.path {
+        dir = dir
+            .join(path)
+            .canonicalize()
+            .context("Could not get canonical path")?;
+    }
+
+    ensure!(dir.is_dir(), "{} is not a directory", dir.display());
+
+    let hopfile = HopFile::find(dir.clone()).await;
+
+    let deployment_id = match options.deployment {
+        Some(id) => Some(id),
+
+        None => match &hopfile {
+            Some(hopfile) => {
+                let environment = hopfile.environment(
+                    options.environment.as_deref(),
+                    state.ctx.default_environment.as_deref(),
+                )?;
+
+                Some(
+                    environment
+                        .map(|(_, environment)| &environment.config)
+                        .unwrap_or(&hopfile.config)
+                        .deployment_id
+                        .clone(),
+                )
+            }
+
+            None => None,
+        },
+    };
+
+    if let Some(hopfile) = &hopfile {
+        dir = hopfile
+            .path
+            .parent()
+            .context("Could not get the parent dir from the hop file location")?
+            .to_path_buf();
+    }
+
+    ensure!(
+        !options.run || options.platforms.len() <= 1,
+        "`--run` can not be used when building for multiple platforms"
+    );
+
+    ensure!(
+        deployment_id.is_some() || !options.run,
+        "`--run` needs a deployment, link one with `hop link` or use `--deployment`"
+    );
+
+    // only talk to the api for linked directories, so plain builds work offline
+    let deployment = match deployment_id {
+        Some(id) => {
+            state.login(None).await?;
+
+            Some(get_deployment(&state.http, &id).await?)
+        }
+
+        None => None,
+    };
+
+    let image = match (options.tag, &deployment) {
+        (Some(tag), _) => tag,
+        (None, Some(deployment)) => deployment.config.image.name.clone(),
+        (None, None) => format!(
+            "hop-local/{}",
+            dir.file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or("app")
+                .to_lowercase()
+        ),
+    };
+
+    let envs = deployment.as_ref().map(build_env).unwrap_or_default();
+
+    let engine = ContainerEngine::from_context(&state.ctx).await?;
+
+    log::info!("Building {} as `{image}` with {engine}", dir.display());
+
+    build_image(engine, &image, dir, &envs, &options.platforms).await?;
+
+    println!();
+    log::info!("Built image `{image}`");
+
+    let Some(deployment) = deployment.filter(|_| options.run) else {
+        return Ok(());
+    };
+
+    let mut ports = get_all_gateways(&state.http, &deployment.id)
+        .await?
+        .into_iter()
+        .filter_map(|gateway| gateway.target_port)
+        .collect::<Vec<_>>();
+
+    ports.sort_unstable();
+    ports.dedup();
+
+    for port in &ports {
+        log::info!("Publishing port {port}");
+    }
+
+    run_image(
+        engine,
+        &image,
+        &RunOptions {
+            env: envs,
+            ports,
+            ..Default::default()
+        },
+    )
+    .await
+}
+
+/// Env of the deployment, secrets are left out since they only resolve on Hop
+fn build_env(deployment: &Deployment) -> HashMap<String, String> {
+    deployment
+        .config
+        .env
+        .iter()
+        .filter(|(key, value)| {
+            let is_secret = get_secret_name(value).is_some();
+
+            if is_secret {
+                log::warn!("Skipping `{key}`, secrets are not available in local builds");
+            }
+
+            !is_secret
+        })
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect()
+}
@@ -4,8 +4,8 @@ mod list;
 mod message;
 mod subscribe;
 mod tokens;
-mod types;
-mod utils;
+pub mod types;
+pub mod utils;
 
 use anyhow::Result;
 use clap::Parser;
@@ -1,17 +1,12 @@
-use std::env::temp_dir;
-
 use anyhow::{ensure, Context, Result};
 use clap::Parser;
 use futures_util::StreamExt;
-use tokio::fs;
-use tokio::process::Command;
 
 use super::utils::{format_containers, format_logs, get_all_containers, get_container_logs};
 use crate::commands::ignite::groups::utils::fetch_grouped_deployments;
-use crate::config::DEFAULT_EDITOR;
 use crate::state::State;
 use crate::utils::arisu::{ArisuClient, ArisuMessage};
-use crate::utils::in_path;
+use crate::utils::open_in_pager;
 
 #[derive(Debug, Parser)]
 #[clap(about = "Get logs of a container")]
@@ -92,31 +87,11 @@ pub async fn handle(options: Options, state: State) -> Result<()> {
     .await?;
 
     if !options.follow {
-        let temp = temp_dir().join(format!("hop_ignite_logs-{container}.txt"));
-
-        fs::write(
-            &temp,
-            format_logs(&logs, false, options.timestamps, options.details).join("\n"),
+        return open_in_pager(
+            &format!("ignite_logs-{container}"),
+            &format_logs(&logs, false, options.timestamps, options.details).join("\n"),
         )
-        .await?;
-
-        let editor = if in_path("less").await {
-            "less".to_string()
-        } else {
-            std::env::var("EDITOR")
-                .or_else(|_| std::env::var("VISUAL"))
-                .unwrap_or_else(|_| DEFAULT_EDITOR.to_string())
-        };
-
-        log::info!("Opening logs in `{editor}`");
-
-        if let Err(e) = Command::new(editor).arg(&temp).status().await {
-            log::warn!("Failed to open logs: {}", e);
-        }
-
-        fs::remove_file(&temp).await?;
-
-        return Ok(());
+        .await;
     }
 
     println!(
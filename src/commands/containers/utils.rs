@@ -93,6 +93,20 @@ pub async fn get_container_logs(
     Ok(response.logs)
 }
 
+/// Share of log lines that were logged as errors, stderr only counts when `count_stderr` is set
+pub fn error_rate(logs: &[Log], count_stderr: bool) -> f64 {
+    if logs.is_empty() {
+        return 0.0;
+    }
+
+    let errors = logs
+        .iter()
+        .filter(|log| log.level == "error" || (count_stderr && log.level == "stderr"))
+        .count();
+
+    errors as f64 / logs.len() as f64
+}
+
 pub const UNAVAILABLE_ELEMENT: &str = "-";
 
 pub fn format_containers(containers: &Vec<Container>, title: bool) -> Vec<String> {
@@ -213,3 +227,26 @@ pub fn format_single_metrics(
 
     Ok(buff)
 }
+
+#[cfg(test)]
+mod test {
+    use chrono::Utc;
+
+    use super::*;
+
+    #[test]
+    fn test_error_rate() {
+        let log = |level: &str| Log {
+            timestamp: Utc::now(),
+            level: level.to_string(),
+            message: String::new(),
+        };
+
+        let logs = [log("info"), log("stderr"), log("error"), log("stdout")];
+
+        assert_eq!(error_rate(&[], false), 0.0);
+        assert_eq!(error_rate(&[log("info"), log("stdout")], true), 0.0);
+        assert_eq!(error_rate(&logs, false), 0.25);
+        assert_eq!(error_rate(&logs, true), 0.5);
+    }
+}
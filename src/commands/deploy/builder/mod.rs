@@ -1,38 +1,42 @@
 mod types;
 mod util;
 
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
 
 use anyhow::{bail, Result};
 use leap_client_rs::leap::types::Event;
 use leap_client_rs::LeapEdge;
+use tabwriter::TabWriter;
 use tokio::sync::mpsc::unbounded_channel;
 use tokio::{fs, spawn};
 
-use self::types::BuildEvents;
-use self::util::{builder_post, compress};
+use self::types::{Build, BuildEvents};
+use self::util::{
+    builder_post, compress, confirm_secrets, hash_build_context, walk_build_context, PrefixedOutput,
+};
 use crate::commands::deploy::builder::types::BuildStatus;
-use crate::commands::ignite::builds::utils::cancel_build;
+use crate::commands::ignite::builds::types::BuildState;
+use crate::commands::ignite::builds::utils::{cancel_build, get_all_builds};
 use crate::state::State;
-use crate::utils::urlify;
+use crate::store::builds::{BuildCache, CachedBuild};
+use crate::store::Store;
+use crate::utils::size::user_friendly_size;
+use crate::utils::{on_ctrlc, urlify};
+
+const LARGEST_ENTRIES: usize = 10;
 
 pub async fn build(
     state: &State,
     project_id: &str,
     deployment_id: &str,
     dir: PathBuf,
+    content_hash: Option<&str>,
     leap: &mut LeapEdge,
-) -> Result<()> {
-    // deployment id is used not to colide if the user is deploying multiple items
-    let packed = compress(deployment_id, dir).await?;
-
-    log::info!("Packed to: {packed}");
-
-    let bytes = fs::read(packed.clone()).await?;
-
-    log::info!("Uploading...");
-
-    let build = builder_post(&state.http, deployment_id, bytes).await?;
+) -> Result<Build> {
+    let build = upload(state, deployment_id, dir, content_hash).await?;
 
     let (tx, mut rx) = unbounded_channel();
 
@@ -61,16 +65,219 @@ pub async fn build(
         }
     });
 
-    let ctrlc = tx.clone();
+    on_ctrlc(tx.clone())?;
+
+    log::info!("From Hop builder:");
+
+    let result = watch(
+        leap,
+        project_id,
+        deployment_id,
+        &build.id,
+        None,
+        &HashSet::new(),
+    )
+    .await;
+
+    tx.send("OK").ok();
+
+    result.map(|_| build)
+}
+
+/// Packs the directory and uploads it to the builder
+pub async fn upload(
+    state: &State,
+    deployment_id: &str,
+    dir: PathBuf,
+    content_hash: Option<&str>,
+) -> Result<Build> {
+    // deployment id is used not to colide if the user is deploying multiple items
+    let packed = compress(deployment_id, dir).await?;
 
-    ctrlc::set_handler(move || {
-        ctrlc.send("CANCEL").ok();
-    })?;
+    log::info!("Packed to: {packed}");
+
+    log::info!("Uploading...");
+
+    let started = Instant::now();
+
+    let build = builder_post(&state.http, deployment_id, Path::new(&packed), content_hash).await?;
+
+    log::info!("Uploaded in {:.1}s", started.elapsed().as_secs_f64());
 
     log::info!("Deleting archive...");
     fs::remove_file(packed).await?;
 
-    log::info!("From Hop builder:");
+    Ok(build)
+}
+
+/// Hashes the files that would be packed from the directory
+pub async fn context_hash(dir: &Path) -> Result<String> {
+    hash_build_context(&walk_build_context(dir).await?).await
+}
+
+/// Asks before likely secrets in the directory get uploaded, refuses outside a terminal unless allowed
+pub async fn check_secrets(dir: &Path, allow_secrets: bool) -> Result<()> {
+    confirm_secrets(&walk_build_context(dir).await?, allow_secrets)
+}
+
+/// Finds the build made from the same context, as long as it is still the latest successful one
+pub async fn find_unchanged_build(
+    state: &State,
+    deployment_id: &str,
+    content_hash: &str,
+) -> Result<Option<String>> {
+    let Some(latest) = get_all_builds(&state.http, deployment_id)
+        .await?
+        .into_iter()
+        .find(|build| matches!(build.state, BuildState::Succeeded))
+    else {
+        return Ok(None);
+    };
+
+    if let Some(hash) = latest.metadata.and_then(|metadata| metadata.content_hash) {
+        return Ok((hash == content_hash).then_some(latest.id));
+    }
+
+    // builds without metadata can only be matched by the hashes stored on this machine,
+    // a pending build counts once it is the latest successful one
+    let cache = BuildCache::new().await?;
+
+    let unchanged = [&cache.deployments, &cache.pending]
+        .into_iter()
+        .filter_map(|builds| builds.get(deployment_id))
+        .any(|cached| cached.build_id == latest.id && cached.content_hash == content_hash);
+
+    Ok(unchanged.then_some(latest.id))
+}
+
+/// Stores the content hash of a successful build locally, for builds the api has no hash of
+pub async fn remember_build(deployment_id: &str, build_id: &str, content_hash: &str) -> Result<()> {
+    let mut cache = BuildCache::new().await?;
+
+    cache.pending.remove(deployment_id);
+    cache.deployments.insert(
+        deployment_id.to_string(),
+        CachedBuild {
+            build_id: build_id.to_string(),
+            content_hash: content_hash.to_string(),
+        },
+    );
+
+    cache.save().await?;
+
+    Ok(())
+}
+
+/// Stores the content hash of a build that is still running, it is only
+/// trusted once the build turns out to be the latest successful one
+pub async fn remember_pending_build(
+    deployment_id: &str,
+    build_id: &str,
+    content_hash: &str,
+) -> Result<()> {
+    let mut cache = BuildCache::new().await?;
+
+    cache.pending.insert(
+        deployment_id.to_string(),
+        CachedBuild {
+            build_id: build_id.to_string(),
+            content_hash: content_hash.to_string(),
+        },
+    );
+
+    cache.save().await?;
+
+    Ok(())
+}
+
+/// Moves the hash of a detached build over once the build is known to have succeeded
+pub async fn confirm_pending_build(deployment_id: &str, build_id: &str) -> Result<()> {
+    let mut cache = BuildCache::new().await?;
+
+    let Some(pending) = cache
+        .pending
+        .remove(deployment_id)
+        .filter(|pending| pending.build_id == build_id)
+    else {
+        return Ok(());
+    };
+
+    cache.deployments.insert(deployment_id.to_string(), pending);
+
+    cache.save().await?;
+
+    Ok(())
+}
+
+/// Packs the directory without uploading it and prints what would be sent to the builder
+pub async fn inspect(dir: PathBuf, list_files: bool) -> Result<()> {
+    let files = walk_build_context(&dir).await?;
+
+    let mut tw = TabWriter::new(vec![]);
+
+    if list_files {
+        writeln!(tw, "SIZE\tPATH")?;
+
+        for file in files.iter().filter(|file| file.is_file) {
+            writeln!(
+                tw,
+                "{}\t{}",
+                user_friendly_size(file.size)?,
+                file.path.display()
+            )?;
+        }
+
+        writeln!(tw)?;
+    }
+
+    // group by the top level entry so big directories stand out
+    let mut entries = HashMap::<PathBuf, u64>::new();
+
+    for file in &files {
+        if let Some(top) = file.path.components().next() {
+            *entries.entry(PathBuf::from(top.as_os_str())).or_default() += file.size;
+        }
+    }
+
+    let mut entries = entries.into_iter().collect::<Vec<_>>();
+    entries.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+    writeln!(tw, "LARGEST ENTRIES\tSIZE")?;
+
+    for (path, size) in entries.iter().take(LARGEST_ENTRIES) {
+        writeln!(tw, "{}\t{}", path.display(), user_friendly_size(*size)?)?;
+    }
+
+    print!("{}", String::from_utf8(tw.into_inner()?)?);
+
+    // only warns, nothing is uploaded
+    confirm_secrets(&files, true)?;
+
+    let packed = compress("dry-run", dir).await?;
+    let compressed = fs::metadata(&packed).await?.len();
+    fs::remove_file(packed).await?;
+
+    log::info!(
+        "{} files, {} uncompressed, {} compressed",
+        files.iter().filter(|file| file.is_file).count(),
+        user_friendly_size(files.iter().map(|file| file.size).sum())?,
+        user_friendly_size(compressed)?
+    );
+
+    Ok(())
+}
+
+/// Follows the events of a build until it is pushed, optionally prefixing every line of output,
+/// progress already shown from the stored logs is passed in `seen` to not print it twice
+pub async fn watch(
+    leap: &mut LeapEdge,
+    project_id: &str,
+    deployment_id: &str,
+    build_id: &str,
+    prefix: Option<String>,
+    seen: &HashSet<String>,
+) -> Result<()> {
+    let mut output = PrefixedOutput::new(prefix);
 
     while let Some(event) = leap.listen().await {
         if let Event::Message(capsuled) = event {
@@ -84,23 +291,23 @@ pub async fn build(
 
             match build_data {
                 BuildEvents::BuildCreate(build_create) => {
-                    if build_create.build.id == build.id {
-                        println!("Validating build...");
+                    if build_create.build.id == build_id {
+                        output.println("Validating build...");
                     }
                 }
 
                 BuildEvents::BuildUpdate(build_update) => {
-                    if build_update.build.id == build.id {
+                    if build_update.build.id == build_id {
                         match build_update.build.state {
                             // initial state from create
                             BuildStatus::Validating => {}
 
                             BuildStatus::Pending => {
-                                println!("Build has been successfully validated, building...");
+                                output
+                                    .println("Build has been successfully validated, building...");
                             }
 
                             BuildStatus::ValidationFailed => {
-                                tx.send("OK").ok();
                                 leap.close().await;
 
                                 // this **should** be present if the status is validation failed
@@ -117,14 +324,13 @@ pub async fn build(
                 }
 
                 BuildEvents::BuildProgress(build_progress) => {
-                    if build_progress.build_id == build.id {
-                        print!("{}", build_progress.log);
+                    if build_progress.build_id == build_id && !seen.contains(&build_progress.id) {
+                        output.print(&build_progress.log);
                     }
                 }
 
                 BuildEvents::BuildCancelled(build_cancelled) => {
-                    if build_cancelled.build_id == build.id {
-                        tx.send("OK").ok();
+                    if build_cancelled.build_id == build_id {
                         leap.close().await;
 
                         bail!("Build cancelled");
@@ -132,8 +338,8 @@ pub async fn build(
                 }
 
                 BuildEvents::PushSuccess(build_complete) => {
-                    if build_complete.build_id == build.id {
-                        tx.send("OK").ok();
+                    if build_complete.build_id == build_id {
+                        output.flush();
 
                         println!();
 
@@ -144,16 +350,18 @@ pub async fn build(
                 }
 
                 BuildEvents::PushFailure(build_failure) => {
-                    if build_failure.build_id == build.id {
+                    if build_failure.build_id == build_id {
                         leap.close().await;
 
+                        output.flush();
+
                         println!();
 
                         bail!(
                                 "Push failed, for help contact us on {} and mention the deployment id: {} and build id: {}",
                                 urlify("https://discord.gg/hop"),
                                 deployment_id,
-                                build.id
+                                build_id
                             );
                     }
                 }
@@ -161,7 +369,7 @@ pub async fn build(
         }
     }
 
-    tx.send("OK").ok();
+    output.flush();
 
     Ok(())
 }
@@ -1,41 +1,101 @@
 use std::env::temp_dir;
+use std::io::{stdin, IsTerminal};
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, ensure, Context, Result};
 use async_compression::tokio::write::GzipEncoder;
+use console::Term;
+use futures_util::stream;
 use hyper::Method;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
 use ignore::WalkBuilder;
 use reqwest::multipart::{Form, Part};
-use tokio::fs::File;
-use tokio::io::AsyncWriteExt;
+use reqwest::Body;
+use sha1::{Digest, Sha1};
+use tokio::fs::{self, File};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::time::sleep;
 use tokio_tar::Builder as TarBuilder;
 
 use super::types::{Build, SingleBuild};
 use crate::commands::deploy::HOP_BUILD_BASE_URL;
+use crate::commands::ignite::builds::types::BuildMetadata;
 use crate::state::http::HttpClient;
 use crate::store::hopfile::VALID_HOP_FILENAMES;
+use crate::utils::size::user_friendly_size;
 
-pub async fn builder_post(http: &HttpClient, deployment_id: &str, bytes: Vec<u8>) -> Result<Build> {
-    let multipart = Form::new().part(
-        "file",
-        Part::bytes(bytes)
-            .file_name("deployment.tar.gz")
-            .mime_str("application/x-gzip")?,
-    );
+// how many times an upload is attempted before giving up
+const UPLOAD_ATTEMPTS: u32 = 3;
+const UPLOAD_CHUNK_SIZE: usize = 64 * 1024;
+// redraw the progress bar at most this often
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+const PROGRESS_BAR_WIDTH: usize = 25;
 
+/// Streams the packed archive to the builder, retrying failed uploads with the same archive
+///
+/// The content hash is stored with the build so later deploys, from any machine, can skip unchanged contexts
+pub async fn builder_post(
+    http: &HttpClient,
+    deployment_id: &str,
+    archive: &Path,
+    content_hash: Option<&str>,
+) -> Result<Build> {
     let builder_uri =
         std::env::var("BUILDER_URL").unwrap_or_else(|_| HOP_BUILD_BASE_URL.to_string());
 
-    let response = http
-        .client
-        .request(
-            Method::POST,
-            format!("{builder_uri}/deployments/{deployment_id}/builds",).as_str(),
-        )
-        .header("content_type", "multipart/form-data".to_string())
-        .multipart(multipart)
-        .send()
-        .await?;
+    let mut attempt = 1;
+
+    let response = loop {
+        let file = File::open(archive).await?;
+        let size = file.metadata().await?.len();
+
+        let mut multipart = Form::new().part(
+            "file",
+            Part::stream_with_length(upload_stream(file, size), size)
+                .file_name("deployment.tar.gz")
+                .mime_str("application/x-gzip")?,
+        );
+
+        if let Some(content_hash) = content_hash {
+            let metadata = BuildMetadata {
+                content_hash: Some(content_hash.to_string()),
+            };
+
+            multipart = multipart.text("metadata", serde_json::to_string(&metadata)?);
+        }
+
+        let response = http
+            .client
+            .request(
+                Method::POST,
+                format!("{builder_uri}/deployments/{deployment_id}/builds",).as_str(),
+            )
+            .header("content_type", "multipart/form-data".to_string())
+            .multipart(multipart)
+            .send()
+            .await;
+
+        // only network errors and server errors are worth retrying
+        let error = match response {
+            Ok(response) if !response.status().is_server_error() => break response,
+            Ok(response) if attempt == UPLOAD_ATTEMPTS => break response,
+            Err(err) if attempt == UPLOAD_ATTEMPTS => return Err(err.into()),
+            Ok(response) => format!("builder responded with {}", response.status()),
+            Err(err) => err.to_string(),
+        };
+
+        let delay = Duration::from_secs(2u64.pow(attempt));
+
+        log::warn!(
+            "Upload failed: {error}, retrying in {}s ({attempt}/{UPLOAD_ATTEMPTS})",
+            delay.as_secs()
+        );
+
+        sleep(delay).await;
+
+        attempt += 1;
+    };
 
     let build = http
         .handle_response::<SingleBuild>(response)
@@ -46,6 +106,116 @@ pub async fn builder_post(http: &HttpClient, deployment_id: &str, bytes: Vec<u8>
     Ok(build)
 }
 
+/// Reads the file in chunks, reporting the progress as they are sent
+fn upload_stream(file: File, size: u64) -> Body {
+    let progress = UploadProgress::new(size);
+
+    Body::wrap_stream(stream::unfold(
+        (file, progress),
+        |(mut file, mut progress)| async move {
+            let mut chunk = vec![0; UPLOAD_CHUNK_SIZE];
+
+            match file.read(&mut chunk).await {
+                Ok(0) => None,
+
+                Ok(read) => {
+                    chunk.truncate(read);
+                    progress.advance(read as u64);
+
+                    Some((Ok(chunk), (file, progress)))
+                }
+
+                Err(err) => Some((Err(err), (file, progress))),
+            }
+        },
+    ))
+}
+
+/// Progress bar for uploads, only drawn when stderr is a terminal
+pub struct UploadProgress {
+    term: Term,
+    total: u64,
+    sent: u64,
+    started: Instant,
+    last_draw: Option<Instant>,
+}
+
+impl UploadProgress {
+    pub fn new(total: u64) -> Self {
+        Self {
+            term: Term::stderr(),
+            total,
+            sent: 0,
+            started: Instant::now(),
+            last_draw: None,
+        }
+    }
+
+    pub fn advance(&mut self, bytes: u64) {
+        self.sent += bytes;
+
+        if !self.term.is_term() {
+            return;
+        }
+
+        let done = self.sent >= self.total;
+
+        if !done
+            && self
+                .last_draw
+                .is_some_and(|last| last.elapsed() < PROGRESS_INTERVAL)
+        {
+            return;
+        }
+
+        self.last_draw = Some(Instant::now());
+
+        self.term.clear_line().ok();
+        self.term.write_str(&self.render()).ok();
+    }
+
+    fn render(&self) -> String {
+        let ratio = if self.total == 0 {
+            1.0
+        } else {
+            (self.sent as f64 / self.total as f64).min(1.0)
+        };
+
+        let filled = (ratio * PROGRESS_BAR_WIDTH as f64) as usize;
+
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let rate = if elapsed > 0.0 {
+            (self.sent as f64 / elapsed) as u64
+        } else {
+            0
+        };
+
+        let eta = self
+            .total
+            .saturating_sub(self.sent)
+            .checked_div(rate)
+            .map_or_else(|| "-".to_string(), |secs| format!("{secs}s"));
+
+        format!(
+            "[{}{}] {}/{} {}/s ETA {eta}",
+            "#".repeat(filled),
+            "-".repeat(PROGRESS_BAR_WIDTH - filled),
+            user_friendly_size(self.sent).unwrap_or_default(),
+            user_friendly_size(self.total).unwrap_or_default(),
+            user_friendly_size(rate).unwrap_or_default(),
+        )
+    }
+}
+
+impl Drop for UploadProgress {
+    fn drop(&mut self) {
+        // leave the terminal clean for the next log line
+        if self.term.is_term() && self.last_draw.is_some() {
+            self.term.clear_line().ok();
+        }
+    }
+}
+
 // default ignore list for tar files
 static DEFAULT_IGNORE_LIST: &[&str] = &[
     ".git",
@@ -56,41 +226,143 @@ static DEFAULT_IGNORE_LIST: &[&str] = &[
     ".vscode",
 ];
 
+// likely secrets that should not end up in a build context
+static SECRET_FILE_NAMES: &[&str] = &["id_rsa", "id_dsa", "id_ecdsa", "id_ed25519"];
+static SECRET_EXTENSIONS: &[&str] = &["pem", "key", "p12", "pfx"];
+// templates of env files are safe to upload
+static ENV_TEMPLATE_SUFFIXES: &[&str] = &[".example", ".sample", ".template"];
+
+#[derive(Debug, Clone)]
+pub struct ContextFile {
+    /// path on the local file system
+    pub full_path: PathBuf,
+    /// path relative to the root of the build context
+    pub path: PathBuf,
+    /// directories are packed too, to keep empty ones
+    pub is_file: bool,
+    /// size in bytes, 0 for directories
+    pub size: u64,
+}
+
 // compress stuff
 pub async fn compress(id: &str, base_dir: PathBuf) -> Result<String> {
     let base_folder_name = Path::new(&id);
     let archive_path = temp_dir().join(format!("hop_{id}.tar.gz"));
 
+    log::info!("Finding files to compress...");
+
+    let files = walk_build_context(&base_dir).await?;
+
     // tarball gunzip stuff
     let writer = File::create(archive_path.clone()).await?;
     let writer = GzipEncoder::new(writer);
     let mut archive = TarBuilder::new(writer);
     archive.follow_symlinks(true);
 
-    log::info!("Finding files to compress...");
+    // add all found files to the tarball
+    for file in files {
+        log::debug!("Adding {} to tarball", file.full_path.display());
+
+        archive
+            .append_path_with_name(&file.full_path, &(*base_folder_name).join(&file.path))
+            .await?;
+    }
+
+    let mut buff = archive.into_inner().await?;
+    buff.shutdown().await?;
+    let mut buff = buff.into_inner();
+    buff.shutdown().await?;
 
-    let mut walker = WalkBuilder::new(base_dir.clone());
+    Ok(archive_path.to_str().unwrap().into())
+}
+
+/// Likely secrets are only uploaded when allowed, or after confirming it in a terminal
+pub fn confirm_secrets(files: &[ContextFile], allow_secrets: bool) -> Result<()> {
+    let secrets = files
+        .iter()
+        .filter(|file| file.is_file && is_likely_secret(&file.path))
+        .map(|file| file.path.display().to_string())
+        .collect::<Vec<_>>();
+
+    if secrets.is_empty() {
+        return Ok(());
+    }
+
+    log::warn!(
+        "The following files look like secrets: {}",
+        secrets.join(", ")
+    );
+    log::warn!("Add them to a `.hopignore` file to exclude them from the build");
+
+    if allow_secrets {
+        return Ok(());
+    }
+
+    ensure!(
+        stdin().is_terminal(),
+        "Refusing to upload likely secrets, pass `--allow-secrets` to upload them anyway"
+    );
+
+    if !dialoguer::Confirm::new()
+        .with_prompt("Upload them to the builder anyway?")
+        .default(false)
+        .interact_opt()?
+        .unwrap_or(false)
+    {
+        bail!("Aborted by user");
+    }
+
+    Ok(())
+}
+
+/// Walks the build context the same way it gets packed for the builder
+pub async fn walk_build_context(base_dir: &Path) -> Result<Vec<ContextFile>> {
+    let mut walker = WalkBuilder::new(base_dir);
     walker.add_ignore(create_global_ignore_file().await?);
     walker.add_custom_ignore_filename(".hopignore");
     walker.hidden(false).follow_links(true);
 
-    let walker = walker.build();
+    // docker only reads the .dockerignore next to the Dockerfile
+    let dockerignore = base_dir.join(".dockerignore");
 
-    // add all found files to the tarball
-    for entry in walker {
+    if base_dir.join("Dockerfile").exists() && dockerignore.exists() {
+        log::info!("Using .dockerignore to filter files");
+
+        let content = fs::read_to_string(&dockerignore).await?;
+        let matcher = parse_dockerignore(base_dir, &content)?;
+
+        walker.filter_entry(move |entry| {
+            entry.depth() == 0
+                || !matcher
+                    .matched_path_or_any_parents(
+                        entry.path(),
+                        entry.file_type().is_some_and(|kind| kind.is_dir()),
+                    )
+                    .is_ignore()
+        });
+    }
+
+    let mut files = vec![];
+
+    for entry in walker.build() {
         match entry {
             Ok(entry) => {
-                log::debug!("Adding {} to tarball", entry.path().display());
-
                 if VALID_HOP_FILENAMES.contains(&entry.file_name().to_str().unwrap()) {
                     continue;
                 }
 
-                let path = entry.path().strip_prefix(&base_dir).unwrap().to_owned();
+                let is_file = entry.file_type().is_some_and(|kind| kind.is_file());
 
-                archive
-                    .append_path_with_name(entry.path(), &(*base_folder_name).join(&path))
-                    .await?;
+                files.push(ContextFile {
+                    path: entry.path().strip_prefix(base_dir).unwrap().to_owned(),
+                    is_file,
+                    size: if is_file {
+                        entry.metadata().map(|meta| meta.len()).unwrap_or_default()
+                    } else {
+                        0
+                    },
+                    full_path: entry.into_path(),
+                });
             }
             Err(err) => {
                 log::warn!("Error walking: {}", err);
@@ -98,12 +370,95 @@ pub async fn compress(id: &str, base_dir: PathBuf) -> Result<String> {
         }
     }
 
-    let mut buff = archive.into_inner().await?;
-    buff.shutdown().await?;
-    let mut buff = buff.into_inner();
-    buff.shutdown().await?;
+    Ok(files)
+}
 
-    Ok(archive_path.to_str().unwrap().into())
+/// Hashes the paths and contents of the build context, ignoring ordering and timestamps
+pub async fn hash_build_context(files: &[ContextFile]) -> Result<String> {
+    let mut files = files.iter().collect::<Vec<_>>();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let mut hasher = Sha1::new();
+    let mut chunk = vec![0; UPLOAD_CHUNK_SIZE];
+
+    for file in files {
+        // normalize separators so the hash is the same on every platform
+        let path = file
+            .path
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        hasher.update(path.as_bytes());
+        hasher.update([0]);
+
+        if !file.is_file {
+            continue;
+        }
+
+        hasher.update(file.size.to_le_bytes());
+
+        let mut reader = File::open(&file.full_path).await?;
+
+        loop {
+            let read = reader.read(&mut chunk).await?;
+
+            if read == 0 {
+                break;
+            }
+
+            hasher.update(&chunk[..read]);
+        }
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Builds a matcher with `.dockerignore` semantics, all patterns are relative to the context root
+pub fn parse_dockerignore(base_dir: &Path, content: &str) -> Result<Gitignore> {
+    let mut builder = GitignoreBuilder::new(base_dir);
+
+    for line in content.lines() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (negate, pattern) = match line.strip_prefix('!') {
+            Some(pattern) => ("!", pattern),
+            None => ("", line),
+        };
+
+        let pattern = pattern.trim_start_matches("./").trim_start_matches('/');
+
+        builder.add_line(None, &format!("{negate}/{pattern}"))?;
+    }
+
+    Ok(builder.build()?)
+}
+
+pub fn is_likely_secret(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return false;
+    };
+
+    if name == ".env" {
+        return true;
+    }
+
+    if name.starts_with(".env.") {
+        return !ENV_TEMPLATE_SUFFIXES
+            .iter()
+            .any(|suffix| name.ends_with(suffix));
+    }
+
+    SECRET_FILE_NAMES.contains(&name)
+        || path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| SECRET_EXTENSIONS.contains(&ext))
 }
 
 async fn create_global_ignore_file() -> Result<PathBuf> {
@@ -119,3 +474,151 @@ async fn create_global_ignore_file() -> Result<PathBuf> {
 
     Ok(path)
 }
+
+/// Prints build output, prefixing every line when multiple builds share the terminal
+pub struct PrefixedOutput {
+    prefix: Option<String>,
+    buffer: String,
+}
+
+impl PrefixedOutput {
+    pub fn new(prefix: Option<String>) -> Self {
+        Self {
+            prefix,
+            buffer: String::new(),
+        }
+    }
+
+    pub fn print(&mut self, text: &str) {
+        let Some(prefix) = &self.prefix else {
+            print!("{text}");
+            return;
+        };
+
+        self.buffer.push_str(text);
+
+        // only complete lines get printed so lines of different builds don't mix
+        while let Some(idx) = self.buffer.find('\n') {
+            println!("{prefix} {}", self.buffer[..idx].trim_end_matches('\r'));
+
+            self.buffer.drain(..=idx);
+        }
+    }
+
+    pub fn println(&mut self, line: &str) {
+        self.print(&format!("{line}\n"));
+    }
+
+    pub fn flush(&mut self) {
+        if !self.buffer.is_empty() {
+            self.print("\n");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_dockerignore() {
+        let root = Path::new("/app");
+
+        let matcher = parse_dockerignore(
+            root,
+            "# comment\nnode_modules\n*.log\n./dist\n**/*.tmp\n!keep.log\n",
+        )
+        .unwrap();
+
+        let ignored = |path: &str, is_dir: bool| {
+            matcher
+                .matched_path_or_any_parents(root.join(path), is_dir)
+                .is_ignore()
+        };
+
+        assert!(ignored("node_modules", true));
+        assert!(ignored("node_modules/react/index.js", false));
+        assert!(ignored("error.log", false));
+        assert!(ignored("dist/index.js", false));
+        assert!(ignored("src/cache/file.tmp", false));
+
+        // patterns are anchored to the root of the context
+        assert!(!ignored("src/node_modules", true));
+        assert!(!ignored("logs/error.log", false));
+        assert!(!ignored("keep.log", false));
+    }
+
+    #[test]
+    fn test_upload_progress_render() {
+        let mut progress = UploadProgress::new(2048);
+        progress.advance(1024);
+
+        let rendered = progress.render();
+
+        assert!(rendered.starts_with(&format!("[{}{}]", "#".repeat(12), "-".repeat(13))));
+        assert!(rendered.contains("1KB/2KB"));
+    }
+
+    #[tokio::test]
+    async fn test_hash_build_context() {
+        let root = temp_dir().join("hop_test_hash_build_context");
+        fs::create_dir_all(root.join("src")).await.unwrap();
+        fs::write(root.join("src/main.rs"), "fn main() {}")
+            .await
+            .unwrap();
+        fs::write(root.join("Dockerfile"), "FROM scratch")
+            .await
+            .unwrap();
+
+        let files = walk_build_context(&root).await.unwrap();
+        let hash = hash_build_context(&files).await.unwrap();
+
+        // ordering of the walk does not matter
+        let reversed = files.iter().rev().cloned().collect::<Vec<_>>();
+        assert_eq!(hash, hash_build_context(&reversed).await.unwrap());
+
+        fs::write(root.join("src/main.rs"), "fn main() { }")
+            .await
+            .unwrap();
+        let files = walk_build_context(&root).await.unwrap();
+        assert_ne!(hash, hash_build_context(&files).await.unwrap());
+
+        fs::remove_dir_all(root).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_walk_build_context_kinds() {
+        let root = temp_dir().join("hop_test_walk_build_context_kinds");
+        fs::create_dir_all(root.join("app")).await.unwrap();
+        fs::write(root.join("app/__init__.py"), "").await.unwrap();
+        fs::write(root.join(".env"), "TOKEN=1").await.unwrap();
+
+        let files = walk_build_context(&root).await.unwrap();
+        let kind = |path: &str| {
+            files
+                .iter()
+                .find(|file| file.path == Path::new(path))
+                .map(|file| file.is_file)
+        };
+
+        // empty files are still files
+        assert_eq!(kind("app/__init__.py"), Some(true));
+        assert_eq!(kind("app"), Some(false));
+
+        assert!(confirm_secrets(&files, true).is_ok());
+
+        fs::remove_dir_all(root).await.unwrap();
+    }
+
+    #[test]
+    fn test_is_likely_secret() {
+        assert!(is_likely_secret(Path::new(".env")));
+        assert!(is_likely_secret(Path::new("config/.env.production")));
+        assert!(is_likely_secret(Path::new("certs/server.pem")));
+        assert!(is_likely_secret(Path::new(".ssh/id_rsa")));
+
+        assert!(!is_likely_secret(Path::new(".env.example")));
+        assert!(!is_likely_secret(Path::new(".ssh/id_rsa.pub")));
+        assert!(!is_likely_secret(Path::new("src/env.rs")));
+    }
+}
@@ -1,18 +1,30 @@
-mod types;
-mod util;
+pub mod types;
+pub mod util;
 
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::env::temp_dir;
+use std::io::{stdin, stdout, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
 
-use anyhow::{bail, Result};
+use anyhow::{bail, ensure, Result};
 use tokio::fs;
 use tokio::process::Command;
 
+use self::types::RunOptions;
+use self::util::{
+    install_nixpacks, installed_nixpacks_version, is_ignite_platform, is_same_nixpacks_version,
+    latest_nixpacks_version, terminal_flags,
+};
 use crate::commands::auth::docker;
-use crate::commands::deploy::local::util::install_nixpacks;
 use crate::state::State;
 use crate::store::utils::home_path;
-use crate::utils::in_path;
+use crate::utils::engine::ContainerEngine;
+
+/// Ignite only runs images built for this platform
+pub const IGNITE_PLATFORM: &str = "linux/amd64";
+
+const BUILDX_BUILDER: &str = "hop-multi-platform";
 
 const NIXPACKS_OVERRIDE: &str = "NIXPACKS_BIN";
 
@@ -27,19 +39,88 @@ pub async fn build(
     image: &str,
     dir: PathBuf,
     envs: &HashMap<String, String>,
+    platforms: &[String],
 ) -> Result<()> {
-    if !in_path("docker").await {
-        bail!("Docker is not installed, it is required to use nixpacks");
-    }
+    let engine = ContainerEngine::from_context(&state.ctx).await?;
 
     let current_user = state.ctx.current.clone().unwrap();
 
     docker::login(
+        engine,
         &current_user.email,
         state.auth.authorized.get(&current_user.id).unwrap(),
     )
     .await?;
 
+    let pushed = build_for_platforms(engine, image, dir, envs, platforms, true).await?;
+
+    println!();
+
+    if !pushed {
+        let command = engine.command().arg("push").arg(image).status().await?;
+
+        if !command.success() {
+            bail!(
+                "Failed to push image: exit code {}",
+                command.code().unwrap_or(1)
+            );
+        }
+
+        println!();
+    }
+
+    log::info!("Pushed image `{image}`");
+
+    Ok(())
+}
+
+/// Builds the image the same way the Hop builder does, without pushing it
+pub async fn build_image(
+    engine: ContainerEngine,
+    image: &str,
+    dir: PathBuf,
+    envs: &HashMap<String, String>,
+    platforms: &[String],
+) -> Result<()> {
+    build_for_platforms(engine, image, dir, envs, platforms, false).await?;
+
+    Ok(())
+}
+
+/// Builds for the given platforms, defaulting to the one Ignite runs,
+/// returns whether the image was already pushed
+async fn build_for_platforms(
+    engine: ContainerEngine,
+    image: &str,
+    dir: PathBuf,
+    envs: &HashMap<String, String>,
+    platforms: &[String],
+    push: bool,
+) -> Result<bool> {
+    let platforms = if platforms.is_empty() {
+        vec![IGNITE_PLATFORM.to_string()]
+    } else {
+        platforms.to_vec()
+    };
+
+    if !platforms
+        .iter()
+        .any(|platform| is_ignite_platform(platform))
+    {
+        log::warn!(
+            "Ignite runs {IGNITE_PLATFORM} containers, an image built only for {} will not start there",
+            platforms.join(", ")
+        );
+    }
+
+    if platforms.len() > 1 {
+        multi_platform_build(engine, image, dir, envs, &platforms, push).await?;
+
+        return Ok(push);
+    }
+
+    let platform = &platforms[0];
+
     // if the dir has a dockerfile act like a normal docker build
     if fs::metadata(dir.join("Dockerfile")).await.is_ok() {
         let build_args = envs
@@ -47,78 +128,355 @@ pub async fn build(
             .map(|(k, v)| format!("--build-arg={k}={v}"))
             .collect::<Vec<_>>();
 
-        let command = Command::new("docker")
-            // allows us to build a lot more stuff
-            .env("DOCKER_BUILDKIT", "1")
-            .env("DOCKER_SCAN_SUGGEST", "false")
-            .arg("build")
-            .arg(dir)
-            .arg("-t")
-            .arg(image)
-            .arg("--progress=plain")
-            .arg("--platform=linux/amd64")
-            .args(build_args)
-            .status()
-            .await?;
+        engine_build(engine, image, &dir, None, platform, build_args).await?;
 
-        if !command.success() {
-            bail!(
-                "Failed to build docker image: exit code {}",
-                command.code().unwrap_or(1)
-            );
-        }
+        return Ok(false);
+    }
+
+    let nixpacks_path = nixpacks_path().await?;
+
+    if !engine.builds_nixpacks() {
+        let out = nixpacks_plan(&nixpacks_path, image, &dir, envs).await?;
+        let dockerfile = out.join(".nixpacks").join("Dockerfile");
+
+        engine_build(engine, image, &out, Some(&dockerfile), platform, vec![]).await?;
+
+        return Ok(false);
+    }
+
+    let command = Command::new(nixpacks_path)
+        .env("DOCKER_BUILDKIT", "1")
+        .env("DOCKER_SCAN_SUGGEST", "false")
+        .arg("build")
+        .arg("-n")
+        .arg(image)
+        .arg(format!("--platform={platform}"))
+        .args(nixpacks_env_args(envs))
+        .arg(dir)
+        .status()
+        .await?;
+
+    if !command.success() {
+        bail!(
+            "Failed to build image: exit code {}",
+            command.code().unwrap_or(1)
+        );
+    }
+
+    Ok(false)
+}
+
+/// Builds a manifest for several platforms with buildx, such images can not be
+/// loaded into the local image store so they are either pushed or kept in the build cache
+async fn multi_platform_build(
+    engine: ContainerEngine,
+    image: &str,
+    dir: PathBuf,
+    envs: &HashMap<String, String>,
+    platforms: &[String],
+    push: bool,
+) -> Result<()> {
+    ensure!(
+        engine == ContainerEngine::Docker,
+        "Building for multiple platforms needs docker buildx, {engine} is not supported"
+    );
+
+    ensure_buildx_builder().await?;
+
+    let (context, dockerfile, build_args) = if fs::metadata(dir.join("Dockerfile")).await.is_ok() {
+        let build_args = envs
+            .iter()
+            .map(|(k, v)| format!("--build-arg={k}={v}"))
+            .collect::<Vec<_>>();
+
+        (dir, None, build_args)
     } else {
-        // if we do not have a dockerfile we need to build the image
-        // ourselves using nixpacks that are vendored for hop or overridden by
-        // the user with the HOP_NIXPACKS_BIN env var
-        let nixpacks_path = if let Ok(path) = std::env::var(NIXPACKS_OVERRIDE) {
-            PathBuf::from(path)
-        } else {
-            home_path(NIXPACKS_VENDORED_PATH)?
-        };
-
-        if fs::metadata(&nixpacks_path).await.is_err() {
-            log::warn!("Nixpacks binary not found, installing...");
+        let out = nixpacks_plan(&nixpacks_path().await?, image, &dir, envs).await?;
+        let dockerfile = out.join(".nixpacks").join("Dockerfile");
 
-            install_nixpacks(&nixpacks_path).await?;
-        }
+        (out, Some(dockerfile), vec![])
+    };
 
-        let command = Command::new(nixpacks_path)
-            .env("DOCKER_BUILDKIT", "1")
-            .env("DOCKER_SCAN_SUGGEST", "false")
-            .arg("build")
-            .arg("-n")
-            .arg(image)
-            .arg("--platform=linux/amd64")
-            .arg(dir)
-            .status()
-            .await?;
+    let mut command = engine.command();
 
-        if !command.success() {
-            bail!(
-                "Failed to build docker image: exit code {}",
-                command.code().unwrap_or(1)
-            );
-        }
+    command
+        .arg("buildx")
+        .arg("build")
+        .arg(format!("--builder={BUILDX_BUILDER}"))
+        .arg(context)
+        .arg("-t")
+        .arg(image)
+        .arg("--progress=plain")
+        .arg(format!("--platform={}", platforms.join(",")));
+
+    if let Some(dockerfile) = dockerfile {
+        command.arg("-f").arg(dockerfile);
     }
 
-    println!();
+    if push {
+        command.arg("--push");
+    } else {
+        log::warn!("Images for multiple platforms can not be loaded into the local image store, the result is only kept in the buildx cache");
+    }
+
+    let command = command.args(build_args).status().await?;
+
+    if !command.success() {
+        bail!(
+            "Failed to build image for {}: exit code {}",
+            platforms.join(", "),
+            command.code().unwrap_or(1)
+        );
+    }
+
+    Ok(())
+}
+
+/// The default docker driver can not build for several platforms at once
+async fn ensure_buildx_builder() -> Result<()> {
+    let inspect = Command::new("docker")
+        .arg("buildx")
+        .arg("inspect")
+        .arg(BUILDX_BUILDER)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await?;
+
+    if inspect.success() {
+        return Ok(());
+    }
+
+    log::info!("Creating the buildx builder `{BUILDX_BUILDER}` for multi-platform builds");
 
-    let command = Command::new("docker")
-        .arg("push")
+    let create = Command::new("docker")
+        .arg("buildx")
+        .arg("create")
+        .arg("--name")
+        .arg(BUILDX_BUILDER)
+        .arg("--driver=docker-container")
+        .stdout(Stdio::null())
+        .status()
+        .await?;
+
+    if !create.success() {
+        bail!("Failed to create a buildx builder, is docker buildx installed?");
+    }
+
+    Ok(())
+}
+
+// the builder passes the env of the deployment to nixpacks as well
+fn nixpacks_env_args(envs: &HashMap<String, String>) -> Vec<String> {
+    envs.iter().map(|(k, v)| format!("--env={k}={v}")).collect()
+}
+
+/// Lets nixpacks write out the dockerfile it would build, for builds it can not run itself
+async fn nixpacks_plan(
+    nixpacks_path: &Path,
+    image: &str,
+    dir: &Path,
+    envs: &HashMap<String, String>,
+) -> Result<PathBuf> {
+    let out = temp_dir().join(format!("hop_nixpacks_{}", image.replace(['/', ':'], "_")));
+
+    if out.exists() {
+        fs::remove_dir_all(&out).await?;
+    }
+
+    let command = Command::new(nixpacks_path)
+        .arg("build")
+        .arg(dir)
+        .arg("--out")
+        .arg(&out)
+        .args(nixpacks_env_args(envs))
+        .status()
+        .await?;
+
+    if !command.success() {
+        bail!(
+            "Failed to generate the nixpacks plan: exit code {}",
+            command.code().unwrap_or(1)
+        );
+    }
+
+    Ok(out)
+}
+
+async fn engine_build(
+    engine: ContainerEngine,
+    image: &str,
+    dir: &Path,
+    dockerfile: Option<&Path>,
+    platform: &str,
+    args: Vec<String>,
+) -> Result<()> {
+    let mut command = engine.command();
+
+    command.arg("build").arg(dir).arg("-t").arg(image);
+
+    if let Some(dockerfile) = dockerfile {
+        command.arg("-f").arg(dockerfile);
+    }
+
+    if engine.supports_progress() {
+        command.arg("--progress=plain");
+    }
+
+    let command = command
+        .arg(format!("--platform={platform}"))
+        .args(args)
+        .status()
+        .await?;
+
+    if !command.success() {
+        bail!(
+            "Failed to build image with {engine}: exit code {}",
+            command.code().unwrap_or(1)
+        );
+    }
+
+    Ok(())
+}
+
+/// Logs in to the registry and pulls the image
+pub async fn pull_image(state: &State, image: &str) -> Result<ContainerEngine> {
+    let engine = ContainerEngine::from_context(&state.ctx).await?;
+
+    let current_user = state.ctx.current.clone().unwrap();
+
+    docker::login(
+        engine,
+        &current_user.email,
+        state.auth.authorized.get(&current_user.id).unwrap(),
+    )
+    .await?;
+
+    let command = engine
+        .command()
+        .arg("pull")
+        .arg(format!("--platform={IGNITE_PLATFORM}"))
         .arg(image)
         .status()
         .await?;
 
     if !command.success() {
         bail!(
-            "Failed to push image: exit code {}",
+            "Failed to pull image: exit code {}",
             command.code().unwrap_or(1)
         );
     }
 
-    println!();
-    log::info!("Pushed image `{image}`");
+    Ok(engine)
+}
+
+/// Runs an image in the foreground until it exits
+pub async fn run_image(engine: ContainerEngine, image: &str, options: &RunOptions) -> Result<()> {
+    let mut args = vec!["run".to_string(), "--rm".to_string()];
+
+    args.extend(terminal_flags(
+        stdin().is_terminal(),
+        stdout().is_terminal(),
+    ));
+    args.push(format!("--platform={IGNITE_PLATFORM}"));
+
+    args.extend(options.env.iter().map(|(k, v)| format!("--env={k}={v}")));
+
+    args.extend(
+        options
+            .ports
+            .iter()
+            .map(|port| format!("--publish={port}:{port}")),
+    );
+
+    if let Some(cpus) = options.cpus {
+        args.push(format!("--cpus={cpus}"));
+    }
+
+    if let Some(memory) = options.memory {
+        args.push(format!("--memory={memory}b"));
+    }
+
+    if let Some((dir, mount_path)) = &options.volume {
+        args.push(format!("--volume={}:{mount_path}", dir.display()));
+    }
+
+    // docker only takes the binary as the entrypoint, the rest goes before the cmd
+    let mut trailing = vec![];
+
+    if let Some((entrypoint, rest)) = options
+        .entrypoint
+        .as_ref()
+        .and_then(|entrypoint| entrypoint.split_first())
+    {
+        args.push(format!("--entrypoint={entrypoint}"));
+        trailing.extend(rest.iter().cloned());
+    }
+
+    trailing.extend(options.cmd.iter().flatten().cloned());
+
+    let command = engine
+        .command()
+        .args(args)
+        .arg(image)
+        .args(trailing)
+        .status()
+        .await?;
+
+    if !command.success() {
+        bail!("Container exited with code {}", command.code().unwrap_or(1));
+    }
 
     Ok(())
 }
+
+/// Path to the nixpacks binary, installing or updating the vendored one to match the builder
+async fn nixpacks_path() -> Result<PathBuf> {
+    // if we do not have a dockerfile we need to build the image
+    // ourselves using nixpacks that are vendored for hop or overridden by
+    // the user with the HOP_NIXPACKS_BIN env var
+    if let Ok(path) = std::env::var(NIXPACKS_OVERRIDE) {
+        return Ok(PathBuf::from(path));
+    }
+
+    let path = home_path(NIXPACKS_VENDORED_PATH)?;
+
+    let installed = if fs::metadata(&path).await.is_ok() {
+        installed_nixpacks_version(&path).await
+    } else {
+        None
+    };
+
+    let latest = match latest_nixpacks_version().await {
+        Ok(latest) => latest,
+
+        Err(error) if installed.is_some() => {
+            log::warn!("Could not check for nixpacks updates, the builder may use another version: {error}");
+
+            return Ok(path);
+        }
+
+        Err(error) => return Err(error),
+    };
+
+    match installed {
+        Some(installed) if is_same_nixpacks_version(&installed, &latest) => {}
+
+        Some(installed) => {
+            log::info!(
+                "Updating nixpacks from {} to {latest} to match the builder",
+                installed.trim()
+            );
+
+            install_nixpacks(&path, &latest).await?;
+        }
+
+        None => {
+            log::warn!("Nixpacks binary not found, installing...");
+
+            install_nixpacks(&path, &latest).await?;
+        }
+    }
+
+    Ok(path)
+}
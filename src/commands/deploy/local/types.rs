@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::path::PathBuf;
 
 use serde::Deserialize;
 
@@ -12,3 +13,17 @@ pub struct DockerAuth {
     // probably base64 but doesnt matter
     pub auth: String,
 }
+
+/// How to run an image locally, mirroring the config of a deployment
+#[derive(Debug, Default)]
+pub struct RunOptions {
+    pub env: HashMap<String, String>,
+    pub ports: Vec<u16>,
+    pub entrypoint: Option<Vec<String>>,
+    pub cmd: Option<Vec<String>>,
+    pub cpus: Option<f64>,
+    /// memory limit in bytes
+    pub memory: Option<u64>,
+    /// local directory and the path it is mounted at
+    pub volume: Option<(PathBuf, String)>,
+}
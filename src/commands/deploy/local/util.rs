@@ -3,6 +3,7 @@ use std::vec;
 
 use anyhow::{anyhow, bail, ensure, Result};
 use tokio::fs;
+use tokio::process::Command;
 
 use crate::commands::update::types::GithubRelease;
 use crate::commands::update::util::{download, execute_commands, swap_exe_command, unpack};
@@ -12,9 +13,8 @@ use crate::state::http::HttpClient;
 const RELEASE_NIXPACKS_URL: &str = "https://api.github.com/repos/hopinc/nixpacks/releases";
 const BASE_NIXPACKS_URL: &str = "https://github.com/hopinc/nixpacks/releases/download";
 
-pub async fn install_nixpacks(path: &PathBuf) -> Result<()> {
-    log::debug!("Install nixpacks to {path:?}");
-
+/// Latest release of the nixpacks fork, the same version the builder runs
+pub async fn latest_nixpacks_version() -> Result<String> {
     let http = HttpClient::new(None, None);
 
     let response = http
@@ -35,7 +35,36 @@ pub async fn install_nixpacks(path: &PathBuf) -> Result<()> {
         .await
         .map_err(|_| anyhow!("Failed to parse Github release"))?;
 
-    let version = &data.first().unwrap().tag_name;
+    Ok(data
+        .first()
+        .ok_or_else(|| anyhow!("No nixpacks releases found"))?
+        .tag_name
+        .clone())
+}
+
+/// Version reported by the nixpacks binary, `None` if it can not be run
+pub async fn installed_nixpacks_version(path: &PathBuf) -> Option<String> {
+    let output = Command::new(path).arg("--version").output().await.ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    String::from_utf8(output.stdout).ok()
+}
+
+/// Compares the output of `nixpacks --version` with a release tag
+pub fn is_same_nixpacks_version(installed: &str, tag: &str) -> bool {
+    installed
+        .split_whitespace()
+        .last()
+        .is_some_and(|version| version.trim_start_matches('v') == tag.trim_start_matches('v'))
+}
+
+pub async fn install_nixpacks(path: &PathBuf, version: &str) -> Result<()> {
+    log::debug!("Install nixpacks {version} to {path:?}");
+
+    let http = HttpClient::new(None, None);
 
     let platform = get_nixpacks_platform()?;
 
@@ -74,3 +103,69 @@ fn get_nixpacks_platform() -> Result<&'static str> {
         _ => bail!("Unsupported platform"),
     }
 }
+
+/// Validates a platform in the form of `os/arch[/variant]`
+pub fn parse_platform(platform: &str) -> Result<String> {
+    let platform = platform.trim().to_lowercase();
+
+    let parts = platform.split('/').collect::<Vec<_>>();
+
+    ensure!(
+        (2..=3).contains(&parts.len()) && parts.iter().all(|part| !part.is_empty()),
+        "Invalid platform `{platform}`, expected `os/arch` like `linux/arm64`"
+    );
+
+    Ok(platform)
+}
+
+/// Ignite runs linux/amd64 regardless of the variant
+pub fn is_ignite_platform(platform: &str) -> bool {
+    platform.split('/').take(2).eq(["linux", "amd64"])
+}
+
+/// `-i` keeps a terminal's input attached, `-t` also needs the output to be one,
+/// both fail with "the input device is not a TTY" in pipelines
+pub fn terminal_flags(stdin_is_term: bool, stdout_is_term: bool) -> Vec<String> {
+    match (stdin_is_term, stdout_is_term) {
+        (true, true) => vec!["-it".to_string()],
+        (true, false) => vec!["-i".to_string()],
+        (false, _) => vec![],
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_is_same_nixpacks_version() {
+        assert!(is_same_nixpacks_version("nixpacks 1.5.1\n", "v1.5.1"));
+        assert!(is_same_nixpacks_version("nixpacks v1.5.1", "1.5.1"));
+        assert!(!is_same_nixpacks_version("nixpacks 1.5.0", "v1.5.1"));
+        assert!(!is_same_nixpacks_version("", "v1.5.1"));
+    }
+
+    #[test]
+    fn test_parse_platform() {
+        assert_eq!(parse_platform("linux/arm64").unwrap(), "linux/arm64");
+        assert_eq!(parse_platform(" Linux/ARM/v7").unwrap(), "linux/arm/v7");
+        assert!(parse_platform("linux").is_err());
+        assert!(parse_platform("linux/").is_err());
+        assert!(parse_platform("linux/arm/v7/x").is_err());
+    }
+
+    #[test]
+    fn test_terminal_flags() {
+        assert_eq!(terminal_flags(true, true), ["-it"]);
+        assert_eq!(terminal_flags(true, false), ["-i"]);
+        assert!(terminal_flags(false, true).is_empty());
+        assert!(terminal_flags(false, false).is_empty());
+    }
+
+    #[test]
+    fn test_is_ignite_platform() {
+        assert!(is_ignite_platform("linux/amd64"));
+        assert!(is_ignite_platform("linux/amd64/v2"));
+        assert!(!is_ignite_platform("linux/arm64"));
+    }
+}
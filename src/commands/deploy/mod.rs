@@ -1,36 +1,48 @@
 pub mod builder;
 pub mod local;
+mod workspace;
 
 use std::env::current_dir;
 use std::path::PathBuf;
+use std::time::Duration;
 
 use anyhow::{bail, ensure, Context, Result};
 use clap::Parser;
 use leap_client_rs::leap::types::Event;
 use leap_client_rs::{LeapEdge, LeapOptions};
+use tokio::time::timeout;
 
+use self::local::util::parse_platform;
 use crate::commands::auth::docker::HOP_REGISTRY_URL;
 use crate::commands::containers::types::{ContainerOptions, ContainerType};
-use crate::commands::containers::utils::create_containers;
+use crate::commands::containers::utils::{create_containers, get_all_containers};
 use crate::commands::gateways::create::GatewayOptions;
 use crate::commands::gateways::types::{GatewayConfig, GatewayType};
 use crate::commands::gateways::util::{create_gateway, update_gateway_config};
+use crate::commands::ignite::builds::types::BuildState;
+use crate::commands::ignite::builds::utils::get_all_builds;
 use crate::commands::ignite::create::{DeploymentConfig, Options as CreateOptions};
+use crate::commands::ignite::health::utils::wait_for_healthy;
+use crate::commands::ignite::history::utils::record_change;
 use crate::commands::ignite::types::{
     CreateDeployment, Deployment, Image, RestartPolicy, RolloutEvents, RolloutState,
     ScalingStrategy,
 };
 use crate::commands::ignite::utils::{
-    create_deployment, env_file_to_map, get_deployment, rollout, update_deployment_config,
-    WEB_IGNITE_URL,
+    create_deployment, env_file_to_map, get_deployment, promote, rollout, update_deployment,
+    update_deployment_config, WEB_IGNITE_URL,
 };
 use crate::commands::projects::utils::format_project;
 use crate::config::LEAP_PROJECT;
 use crate::state::State;
+use crate::store::history::Change;
 use crate::store::hopfile::HopFile;
-use crate::utils::urlify;
+use crate::store::workspace::Workspace;
+use crate::utils::{parse_duration, urlify};
 
 const HOP_BUILD_BASE_URL: &str = "https://builder.hop.io/v1";
+// how long to wait for rollouts started by the api, like after a promote
+const ROLLOUT_START_TIMEOUT: Duration = Duration::from_secs(60);
 
 #[derive(Debug, Parser)]
 #[clap(about = "Deploy a new container")]
@@ -38,10 +50,16 @@ const HOP_BUILD_BASE_URL: &str = "https://builder.hop.io/v1";
 pub struct Options {
     #[clap(
         name = "dir",
-        help = "Directory to deploy, defaults to current directory"
+        help = "Directory to deploy, defaults to current directory, or the name of a workspace service"
     )]
     path: Option<PathBuf>,
 
+    #[clap(name = "services", help = "Other services of the workspace to deploy")]
+    services: Vec<String>,
+
+    #[clap(long, help = "Deploy all services of the workspace")]
+    all: bool,
+
     #[clap(flatten)]
     config: DeploymentConfig,
 
@@ -62,13 +80,348 @@ pub struct Options {
     )]
     local: bool,
 
+    #[clap(
+        long = "platform",
+        help = "Platform to build for with --local, can be repeated to build a multi-platform image with buildx",
+        value_parser = parse_platform
+    )]
+    platforms: Vec<String>,
+
     #[clap(long, help = "Do not roll out the changes, only build")]
     no_rollout: bool,
+
+    #[clap(
+        long,
+        help = "Environment from the hopfile to deploy to, defaults to the one set with `hop link --default`"
+    )]
+    environment: Option<String>,
+
+    #[clap(
+        long,
+        help = "Pack the directory and show what would be uploaded without deploying"
+    )]
+    dry_run: bool,
+
+    #[clap(long, help = "List every packed file, only with --dry-run")]
+    list_files: bool,
+
+    #[clap(long, help = "Build even if nothing changed since the last build")]
+    force: bool,
+
+    #[clap(
+        long,
+        help = "Upload files that look like secrets, like .env or private keys, without asking"
+    )]
+    allow_secrets: bool,
+
+    #[clap(
+        long,
+        help = "Upload the build and exit without waiting for it, reattach with `hop ignite builds watch`"
+    )]
+    detach: bool,
+
+    #[clap(flatten)]
+    rollout: RolloutOptions,
+}
+
+#[derive(Debug, Parser, Clone, Default)]
+#[group(skip)]
+pub struct RolloutOptions {
+    #[clap(
+        long,
+        help = "Wait for the new containers to pass their health checks after the rollout"
+    )]
+    pub wait_healthy: bool,
+
+    #[clap(
+        long,
+        help = "How long to wait for the containers to become healthy",
+        default_value = "5m",
+        value_parser = parse_duration
+    )]
+    pub timeout: Duration,
+
+    #[clap(
+        long,
+        help = "Promote the previous successful build if the rollout or health checks fail"
+    )]
+    pub auto_rollback: bool,
+}
+
+/// Connects to Leap and subscribes to the project channel for build and rollout events
+pub async fn connect_to_leap(state: &State, project_id: &str) -> Result<LeapEdge> {
+    let mut leap = LeapEdge::new(LeapOptions {
+        token: Some(&state.ctx.current.clone().unwrap().leap_token),
+        project: &std::env::var("LEAP_PROJECT").unwrap_or_else(|_| LEAP_PROJECT.to_string()),
+        ws_url: &std::env::var("LEAP_WS_URL")
+            .unwrap_or_else(|_| LeapOptions::default().ws_url.to_string()),
+    })
+    .await?;
+
+    // all projects should already be subscribed but this is a precaution
+    leap.channel_subscribe(project_id).await?;
+
+    Ok(leap)
+}
+
+/// Follows the events of a rollout until it finishes
+pub async fn wait_for_rollout(
+    leap: &mut LeapEdge,
+    project_id: &str,
+    rollout_id: &str,
+) -> Result<()> {
+    while let Some(event) = leap.listen().await {
+        if let Event::Message(capsuled) = event {
+            if capsuled.channel.as_deref() != Some(project_id) {
+                continue;
+            }
+
+            let Ok(rollout_event) = serde_json::from_value(serde_json::to_value(capsuled.data)?) else {
+                continue;
+            };
+
+            match rollout_event {
+                RolloutEvents::RolloutCreate(event) => {
+                    if rollout_id == event.rollout.id {
+                        log::info!("Rolling out new containers");
+                    }
+                }
+
+                RolloutEvents::RolloutUpdate(event) => {
+                    if rollout_id != event.id {
+                        continue;
+                    }
+
+                    match event.state {
+                        // default state, when created
+                        RolloutState::Pending => {}
+
+                        RolloutState::Finished => {
+                            log::info!("Successfully rolled out new containers");
+
+                            break;
+                        }
+
+                        RolloutState::Failed => {
+                            bail!("Rollout failed");
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Waits for the next rollout of the deployment, for rollouts started by the api like promotes
+pub async fn wait_for_deployment_rollout(
+    leap: &mut LeapEdge,
+    project_id: &str,
+    deployment_id: &str,
+) -> Result<()> {
+    let started = timeout(ROLLOUT_START_TIMEOUT, async {
+        while let Some(event) = leap.listen().await {
+            if let Event::Message(capsuled) = event {
+                if capsuled.channel.as_deref() != Some(project_id) {
+                    continue;
+                }
+
+                let Ok(RolloutEvents::RolloutCreate(event)) = serde_json::from_value(serde_json::to_value(capsuled.data)?) else {
+                    continue;
+                };
+
+                if event.rollout.deployment_id.as_deref() == Some(deployment_id) {
+                    return Ok(Some(event.rollout.id));
+                }
+            }
+        }
+
+        Ok::<_, anyhow::Error>(None)
+    })
+    .await;
+
+    let Ok(rollout_id) = started else {
+        bail!(
+            "No rollout started within {}s, check the containers with `hop ignite containers ls {deployment_id}`",
+            ROLLOUT_START_TIMEOUT.as_secs()
+        );
+    };
+
+    let Some(rollout_id) = rollout_id? else {
+        return Ok(());
+    };
+
+    log::info!("Rolling out new containers");
+
+    wait_for_rollout(leap, project_id, &rollout_id).await
+}
+
+/// Latest successful build of the deployment, used to roll back failed deploys
+pub async fn latest_successful_build(state: &State, deployment_id: &str) -> Result<Option<String>> {
+    Ok(get_all_builds(&state.http, deployment_id)
+        .await?
+        .into_iter()
+        .find(|build| matches!(build.state, BuildState::Succeeded))
+        .map(|build| build.id))
+}
+
+/// Rolls out the deployment and checks the new containers, rolling back to `previous_build` on failure
+pub async fn rollout_with_checks(
+    state: &State,
+    leap: &mut LeapEdge,
+    project_id: &str,
+    deployment_id: &str,
+    options: &RolloutOptions,
+    previous_build: Option<&str>,
+) -> Result<()> {
+    let existing: Vec<String> = if options.wait_healthy {
+        get_all_containers(&state.http, deployment_id)
+            .await?
+            .into_iter()
+            .map(|container| container.id)
+            .collect()
+    } else {
+        vec![]
+    };
+
+    let rollout = rollout(&state.http, deployment_id).await?;
+
+    record_change(
+        state,
+        deployment_id,
+        Change::Rollout {
+            rollout_id: Some(rollout.id.clone()),
+            canary: None,
+        },
+    )
+    .await;
+
+    let result = match wait_for_rollout(leap, project_id, &rollout.id).await {
+        Ok(()) if options.wait_healthy => {
+            wait_for_healthy(
+                &state.http,
+                deployment_id,
+                |container| !existing.contains(&container.id),
+                options.timeout,
+            )
+            .await
+        }
+
+        result => result,
+    };
+
+    let Err(error) = result else {
+        return Ok(());
+    };
+
+    if !options.auto_rollback {
+        return Err(error);
+    }
+
+    let Some(build_id) = previous_build else {
+        log::warn!("No previous successful build to roll back to");
+
+        return Err(error);
+    };
+
+    log::error!("{error}");
+    log::info!("Rolling back to build `{build_id}`");
+
+    promote(&state.http, deployment_id, build_id).await?;
+
+    record_change(
+        state,
+        deployment_id,
+        Change::Promote {
+            build_id: build_id.to_string(),
+        },
+    )
+    .await;
+
+    bail!("Deploy failed, rolled back to build `{build_id}`")
 }
 
 pub async fn handle(options: Options, state: State) -> Result<()> {
     let mut dir = current_dir().context("Could not get current directory")?;
 
+    ensure!(
+        options.dry_run || !options.list_files,
+        "`--list-files` can only be used with `--dry-run`"
+    );
+
+    ensure!(
+        options.local || options.platforms.is_empty(),
+        "`--platform` can only be used with `--local`"
+    );
+
+    ensure!(
+        !options.no_rollout || !(options.rollout.wait_healthy || options.rollout.auto_rollback),
+        "`--wait-healthy` and `--auto-rollback` can not be used with `--no-rollout`"
+    );
+
+    ensure!(
+        !options.detach || !options.local,
+        "`--detach` can not be used with `--local`"
+    );
+
+    ensure!(
+        !options.detach || !(options.rollout.wait_healthy || options.rollout.auto_rollback),
+        "`--wait-healthy` and `--auto-rollback` can not be used with `--detach`, pass them to `hop ignite builds watch` instead"
+    );
+
+    match Workspace::find(dir.clone()).await {
+        Some(workspace) => {
+            let mut services = options.services.clone();
+
+            if let Some(path) = options.path.as_ref().and_then(|path| path.to_str()) {
+                if workspace.services.contains_key(path) {
+                    services.insert(0, path.to_string());
+                } else if !services.is_empty() {
+                    bail!("`{path}` is not a service of the workspace");
+                }
+            }
+
+            if options.all || !services.is_empty() {
+                // an empty list deploys every service
+                if options.all {
+                    services.clear();
+                }
+
+                if options.dry_run {
+                    return workspace::inspect(&workspace, &services, options.list_files).await;
+                }
+
+                ensure!(
+                    !options.detach,
+                    "`--detach` can not be used when deploying multiple services"
+                );
+
+                if !options.local {
+                    workspace::check_secrets(&workspace, &services, options.allow_secrets).await?;
+                }
+
+                return workspace::deploy(
+                    &state,
+                    &workspace,
+                    &services,
+                    options.local.then_some(options.platforms.as_slice()),
+                    options.no_rollout,
+                    options.force,
+                    &options.rollout,
+                )
+                .await;
+            }
+        }
+
+        None => {
+            ensure!(
+                !options.all && options.services.is_empty(),
+                "No workspace file found, create a `hop.workspace.yml` to deploy multiple services"
+            );
+        }
+    }
+
     if let Some(path) = options.path {
         dir = dir
             .join(path)
@@ -78,9 +431,25 @@ pub async fn handle(options: Options, state: State) -> Result<()> {
 
     ensure!(dir.is_dir(), "{} is not a directory", dir.display());
 
+    if options.dry_run {
+        if let Some(hopfile) = HopFile::find(dir.clone()).await {
+            dir = hopfile
+                .path
+                .parent()
+                .context("Could not get the parent dir from the hop file location")?
+                .to_path_buf();
+        }
+
+        log::info!("Inspecting {}", dir.display());
+
+        return builder::inspect(dir, options.list_files).await;
+    }
+
     log::info!("Attempting to deploy {}", dir.display());
 
     let is_visual = options.config == DeploymentConfig::default();
+    // changes to the config need a rollout even if the build is skipped
+    let mut config_updated = false;
 
     let (project, deployment, container_options, existing) = match HopFile::find(dir.clone()).await
     {
@@ -93,19 +462,28 @@ pub async fn handle(options: Options, state: State) -> Result<()> {
 
             log::info!("Found hopfile: {}", hopfile.path.display());
 
+            let environment = hopfile.environment(
+                options.environment.as_deref(),
+                state.ctx.default_environment.as_deref(),
+            )?;
+
+            let config = match environment {
+                Some((name, environment)) => {
+                    log::info!("Using environment `{name}`");
+
+                    &environment.config
+                }
+                None => &hopfile.config,
+            };
+
             // TODO: possible update of deployment if it already exists?
-            let deployment = get_deployment(&state.http, &hopfile.config.deployment_id).await?;
+            let mut deployment = get_deployment(&state.http, &config.deployment_id).await?;
 
             // if deployment exists it's safe to unwrap
             let project = state
                 .ctx
-                .find_project_by_id_or_namespace(&hopfile.config.project_id)
-                .with_context(|| {
-                    format!(
-                        "Could not find project with id {}",
-                        hopfile.config.project_id
-                    )
-                })?;
+                .find_project_by_id_or_namespace(&config.project_id)
+                .with_context(|| format!("Could not find project with id {}", config.project_id))?;
 
             if is_visual {
                 log::warn!("Deployment exists, skipping arguments");
@@ -113,6 +491,43 @@ pub async fn handle(options: Options, state: State) -> Result<()> {
 
             log::info!("Deploying to project {}", format_project(&project));
 
+            if let Some((name, environment)) = environment {
+                if environment.production
+                    && !options.yes
+                    && !dialoguer::Confirm::new()
+                        .with_prompt(format!(
+                            "`{name}` is a production environment, deploy `{}` to it?",
+                            deployment.name
+                        ))
+                        .default(false)
+                        .interact_opt()?
+                        .unwrap_or(false)
+                {
+                    bail!("Aborted by user");
+                }
+
+                let overridden = environment
+                    .env
+                    .iter()
+                    .any(|(key, value)| deployment.config.env.get(key) != Some(value));
+
+                if overridden {
+                    log::info!("Applying env overrides of `{name}`");
+
+                    let mut update = CreateDeployment::from(deployment.clone());
+                    update.name = None;
+                    update.type_ = None;
+                    update.env.extend(environment.env.clone());
+
+                    let before = Box::new(deployment.config.clone());
+
+                    deployment = update_deployment(&state.http, &deployment.id, &update).await?;
+
+                    record_change(&state, &deployment.id, Change::Config { before }).await;
+                    config_updated = true;
+                }
+            }
+
             // TODO: update when autoscaling is supported
             let container_options = ContainerOptions {
                 containers: Some(deployment.container_count),
@@ -124,6 +539,16 @@ pub async fn handle(options: Options, state: State) -> Result<()> {
         }
 
         None => {
+            if let Some(environment) = options.environment {
+                bail!("No hopfile found to deploy environment `{environment}` from");
+            }
+
+            // new deployments get their containers after the build
+            ensure!(
+                !options.detach,
+                "`--detach` can only be used with existing deployments, deploy once without it first"
+            );
+
             log::info!("No hopfile found, creating one");
 
             let project = state.ctx.current_project_error()?;
@@ -226,67 +651,93 @@ pub async fn handle(options: Options, state: State) -> Result<()> {
     };
 
     // connect to leap here so no logs interfere with the deploy
-    let mut leap = LeapEdge::new(LeapOptions {
-        token: Some(&state.ctx.current.clone().unwrap().leap_token),
-        project: &std::env::var("LEAP_PROJECT").unwrap_or_else(|_| LEAP_PROJECT.to_string()),
-        ws_url: &std::env::var("LEAP_WS_URL")
-            .unwrap_or_else(|_| LeapOptions::default().ws_url.to_string()),
-    })
-    .await?;
+    let mut leap = connect_to_leap(&state, &project.id).await?;
 
-    // all projects should already be subscribed but this is a precaution
-    leap.channel_subscribe(&project.id).await?;
+    // the build running before this deploy, to roll back to
+    let previous_build = if existing && options.rollout.auto_rollback {
+        latest_successful_build(&state, &deployment.id).await?
+    } else {
+        None
+    };
+
+    let mut skipped_build = false;
 
     if !options.local {
-        builder::build(&state, &project.id, &deployment.id, dir.clone(), &mut leap).await?;
+        let content_hash = builder::context_hash(&dir).await?;
+
+        let unchanged = if options.force {
+            None
+        } else {
+            builder::find_unchanged_build(&state, &deployment.id, &content_hash).await?
+        };
+
+        if unchanged.is_none() {
+            builder::check_secrets(&dir, options.allow_secrets).await?;
+        }
+
+        match unchanged {
+            Some(build_id) => {
+                log::info!("Nothing changed since build `{build_id}`, skipping the build, use `--force` to build anyway");
+
+                skipped_build = true;
+            }
+
+            None if options.detach => {
+                let build =
+                    builder::upload(&state, &deployment.id, dir.clone(), Some(&content_hash))
+                        .await?;
+
+                // the build may still fail, so it is not remembered as a finished one
+                builder::remember_pending_build(&deployment.id, &build.id, &content_hash).await?;
+
+                leap.close().await;
+
+                log::info!(
+                    "Started build `{}`, run `hop ignite builds watch {} --project {}` to follow it and roll it out",
+                    build.id,
+                    build.id,
+                    project.namespace
+                );
+
+                return Ok(());
+            }
+
+            None => {
+                let build = builder::build(
+                    &state,
+                    &project.id,
+                    &deployment.id,
+                    dir.clone(),
+                    Some(&content_hash),
+                    &mut leap,
+                )
+                .await?;
+
+                builder::remember_build(&deployment.id, &build.id, &content_hash).await?;
+            }
+        }
     } else {
         local::build(
             &state,
             &deployment.config.image.name,
             dir.clone(),
             &deployment.config.env,
+            &options.platforms,
         )
         .await?;
     }
 
     if existing {
-        if deployment.can_rollout() && !options.no_rollout {
-            let rollout = rollout(&state.http, &deployment.id).await?;
-
-            while let Some(event) = leap.listen().await {
-                if let Event::Message(capsuled) = event {
-                    if capsuled.channel.as_deref() != Some(&project.id) {
-                        continue;
-                    }
-
-                    let Ok(rollout_event) = serde_json::from_value(serde_json::to_value(capsuled.data)?) else {
-                        continue;
-                    };
-
-                    match rollout_event {
-                        RolloutEvents::RolloutCreate(event) => {
-                            if rollout.id == event.rollout.id {
-                                log::info!("Rolling out new containers");
-                            }
-                        }
-
-                        RolloutEvents::RolloutUpdate(event) => match event.state {
-                            // default state, when created
-                            RolloutState::Pending => {}
-
-                            RolloutState::Finished => {
-                                log::info!("Successfully rolled out new containers");
-
-                                break;
-                            }
-
-                            RolloutState::Failed => {
-                                bail!("Rollout failed");
-                            }
-                        },
-                    }
-                }
-            }
+        if deployment.can_rollout() && !options.no_rollout && (!skipped_build || config_updated) {
+            rollout_with_checks(
+                &state,
+                &mut leap,
+                &project.id,
+                &deployment.id,
+                &options.rollout,
+                previous_build.as_deref(),
+            )
+            .await?;
         }
     } else if let Some(containers) = container_options.containers {
         if deployment.can_scale() && containers > 0 {
@@ -0,0 +1,327 @@
+use std::collections::HashSet;
+
+use anyhow::{bail, ensure, Context, Result};
+use console::{style, Color};
+use futures_util::future::join_all;
+use tokio::spawn;
+use tokio::sync::mpsc::unbounded_channel;
+
+use super::{
+    builder, connect_to_leap, latest_successful_build, local, rollout_with_checks, RolloutOptions,
+};
+use crate::commands::ignite::builds::utils::cancel_build;
+use crate::commands::ignite::from_compose::utils::order_by_dependencies;
+use crate::commands::ignite::utils::get_deployment;
+use crate::commands::projects::utils::format_project;
+use crate::state::State;
+use crate::store::workspace::{Workspace, WorkspaceService};
+use crate::utils::on_ctrlc;
+
+const PREFIX_COLORS: [Color; 5] = [
+    Color::Cyan,
+    Color::Magenta,
+    Color::Yellow,
+    Color::Green,
+    Color::Blue,
+];
+
+/// Builds the services of a workspace and rolls them out in dependency order, builds on the
+/// builder run in parallel, `local` holds the platforms to build for when building locally instead
+pub async fn deploy(
+    state: &State,
+    workspace: &Workspace,
+    names: &[String],
+    local: Option<&[String]>,
+    no_rollout: bool,
+    force: bool,
+    rollout_options: &RolloutOptions,
+) -> Result<()> {
+    let project = state
+        .ctx
+        .find_project_by_id_or_namespace(&workspace.project_id)
+        .with_context(|| format!("Could not find project with id {}", workspace.project_id))?;
+
+    let services = select_services(workspace, names)?;
+
+    log::info!(
+        "Deploying {} services to project {}",
+        services.len(),
+        format_project(&project)
+    );
+
+    let deployments = join_all(
+        services
+            .iter()
+            .map(|(_, service)| get_deployment(&state.http, &service.deployment_id)),
+    )
+    .await
+    .into_iter()
+    .collect::<Result<Vec<_>>>()?;
+
+    // builds running before this deploy, to roll back to
+    let previous_builds = if rollout_options.auto_rollback {
+        join_all(
+            deployments
+                .iter()
+                .map(|deployment| latest_successful_build(state, &deployment.id)),
+        )
+        .await
+        .into_iter()
+        .collect::<Result<Vec<_>>>()?
+    } else {
+        vec![None; deployments.len()]
+    };
+
+    // services whose build context did not change since their last build
+    let mut skipped = vec![false; services.len()];
+
+    if let Some(platforms) = local {
+        // local builds run one after another, they may prompt to install nixpacks, share
+        // the buildx builder and the engine login, and the engine output is not prefixed
+        for ((name, service), deployment) in services.iter().zip(&deployments) {
+            log::info!("Building `{name}` locally");
+
+            local::build(
+                state,
+                &deployment.config.image.name,
+                workspace.build_context(service),
+                &deployment.config.env,
+                platforms,
+            )
+            .await?;
+        }
+    } else {
+        let hashes = join_all(services.iter().map(|(_, service)| async move {
+            builder::context_hash(&workspace.build_context(service)).await
+        }))
+        .await
+        .into_iter()
+        .collect::<Result<Vec<_>>>()?;
+
+        if !force {
+            for (idx, ((name, service), hash)) in services.iter().zip(&hashes).enumerate() {
+                let unchanged =
+                    builder::find_unchanged_build(state, &service.deployment_id, hash).await?;
+
+                if let Some(build_id) = unchanged {
+                    log::info!("Nothing changed in `{name}` since build `{build_id}`, skipping it");
+
+                    skipped[idx] = true;
+                }
+            }
+        }
+
+        let (to_build, hashes): (Vec<_>, Vec<_>) = services
+            .iter()
+            .zip(hashes)
+            .zip(&skipped)
+            .filter(|(_, skipped)| !**skipped)
+            .map(|((service, hash), _)| (*service, hash))
+            .unzip();
+
+        if to_build.is_empty() {
+            log::info!("Nothing changed, use `--force` to build anyway");
+
+            return Ok(());
+        }
+
+        build_in_parallel(state, workspace, &project.id, &to_build, &hashes).await?;
+    }
+
+    if no_rollout {
+        return Ok(());
+    }
+
+    let mut leap = connect_to_leap(state, &project.id).await?;
+
+    for ((((name, _), deployment), skipped), previous_build) in services
+        .iter()
+        .zip(&deployments)
+        .zip(skipped)
+        .zip(&previous_builds)
+    {
+        if skipped || !deployment.can_rollout() {
+            continue;
+        }
+
+        log::info!("Rolling out `{name}`");
+
+        rollout_with_checks(
+            state,
+            &mut leap,
+            &project.id,
+            &deployment.id,
+            rollout_options,
+            previous_build.as_deref(),
+        )
+        .await?;
+    }
+
+    leap.close().await;
+
+    log::info!("Deployed {} services", services.len());
+
+    Ok(())
+}
+
+/// Shows what would be uploaded for each of the services
+pub async fn inspect(workspace: &Workspace, names: &[String], list_files: bool) -> Result<()> {
+    for (name, service) in select_services(workspace, names)? {
+        let dir = workspace.build_context(service);
+
+        log::info!("Inspecting `{name}` at {}", dir.display());
+
+        builder::inspect(dir, list_files).await?;
+
+        println!();
+    }
+
+    Ok(())
+}
+
+/// Asks before likely secrets of any of the services get uploaded, before the first build starts
+pub async fn check_secrets(
+    workspace: &Workspace,
+    names: &[String],
+    allow_secrets: bool,
+) -> Result<()> {
+    for (_, service) in select_services(workspace, names)? {
+        builder::check_secrets(&workspace.build_context(service), allow_secrets).await?;
+    }
+
+    Ok(())
+}
+
+/// Picks the requested services, or all of them, ordered by their dependencies
+fn select_services<'a>(
+    workspace: &'a Workspace,
+    names: &[String],
+) -> Result<Vec<(&'a String, &'a WorkspaceService)>> {
+    // the whole workspace is ordered so dependencies outside the selection are still checked
+    let mut services = workspace.services.iter().collect::<Vec<_>>();
+
+    order_by_dependencies(&mut services)?;
+
+    if names.is_empty() {
+        return Ok(services);
+    }
+
+    for name in names {
+        ensure!(
+            workspace.services.contains_key(name),
+            "Service `{name}` not found in the workspace"
+        );
+    }
+
+    Ok(services
+        .into_iter()
+        .filter(|(name, _)| names.contains(name))
+        .collect())
+}
+
+async fn build_in_parallel(
+    state: &State,
+    workspace: &Workspace,
+    project_id: &str,
+    services: &[(&String, &WorkspaceService)],
+    hashes: &[String],
+) -> Result<()> {
+    // connect before uploading so no build events are missed
+    let mut leaps = join_all(services.iter().map(|_| connect_to_leap(state, project_id)))
+        .await
+        .into_iter()
+        .collect::<Result<Vec<_>>>()?;
+
+    let builds = join_all(services.iter().zip(hashes).map(|((_, service), hash)| {
+        builder::upload(
+            state,
+            &service.deployment_id,
+            workspace.build_context(service),
+            Some(hash),
+        )
+    }))
+    .await
+    .into_iter()
+    .collect::<Result<Vec<_>>>()?;
+
+    let (tx, mut rx) = unbounded_channel();
+
+    let http = state.http.clone();
+    let build_ids = builds
+        .iter()
+        .map(|build| build.id.clone())
+        .collect::<Vec<_>>();
+
+    spawn(async move {
+        if let Some("CANCEL") = rx.recv().await {
+            log::info!("Cancelling builds...");
+
+            for build_id in build_ids {
+                if cancel_build(&http, &build_id).await.is_err() {
+                    log::error!("Failed to cancel build {build_id}");
+                }
+            }
+
+            std::process::exit(1);
+        }
+    });
+
+    on_ctrlc(tx)?;
+
+    let width = services
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or_default();
+
+    let seen = HashSet::new();
+
+    let results = join_all(
+        services
+            .iter()
+            .zip(&builds)
+            .zip(leaps.iter_mut())
+            .enumerate()
+            .map(|(idx, (((name, service), build), leap))| {
+                let prefix = style(format!("{name:width$} |"))
+                    .fg(PREFIX_COLORS[idx % PREFIX_COLORS.len()])
+                    .to_string();
+
+                builder::watch(
+                    leap,
+                    project_id,
+                    &service.deployment_id,
+                    &build.id,
+                    Some(prefix),
+                    &seen,
+                )
+            }),
+    )
+    .await;
+
+    let mut failed = 0;
+
+    for ((((name, service), build), hash), result) in
+        services.iter().zip(&builds).zip(hashes).zip(results)
+    {
+        match result {
+            Ok(()) => builder::remember_build(&service.deployment_id, &build.id, hash).await?,
+
+            Err(error) => {
+                log::error!("Build of `{name}` failed: {error}");
+
+                failed += 1;
+            }
+        }
+    }
+
+    for leap in leaps.iter_mut() {
+        leap.close().await;
+    }
+
+    if failed > 0 {
+        bail!("{failed} of {} builds failed", services.len());
+    }
+
+    Ok(())
+}
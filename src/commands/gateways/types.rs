@@ -6,7 +6,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::commands::domains::types::Domain;
 
-#[derive(Debug, Serialize, Clone, Default, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
 pub struct GatewayConfig {
     #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
     pub type_: Option<GatewayType>,
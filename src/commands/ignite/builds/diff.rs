@@ -0,0 +1,210 @@
+use anyhow::{bail, Result};
+use clap::Parser;
+use console::style;
+
+use super::types::{Build, FileChange, ImageLayer};
+use super::utils::{
+    build_image_ref, diff_files, get_build, image_files, image_layers, inspect_image, shared_layers,
+};
+use crate::commands::deploy::local::pull_image;
+use crate::commands::ignite::utils::get_deployment;
+use crate::state::State;
+use crate::utils::relative_time;
+use crate::utils::size::user_friendly_size;
+
+// changed files listed without `--all-files`
+const FILES_LIMIT: usize = 50;
+
+#[derive(Debug, Parser)]
+#[clap(about = "Compare the images of two builds")]
+#[group(skip)]
+pub struct Options {
+    #[clap(help = "ID of the older build")]
+    pub old: String,
+
+    #[clap(help = "ID of the newer build")]
+    pub new: String,
+
+    #[clap(
+        long,
+        help = "Only compare the layers, exporting the images can take a while"
+    )]
+    pub no_files: bool,
+
+    #[clap(long, help = "List every changed file")]
+    pub all_files: bool,
+}
+
+pub async fn handle(options: Options, state: State) -> Result<()> {
+    let old = get_build(&state.http, &options.old).await?;
+    let new = get_build(&state.http, &options.new).await?;
+
+    if old.deployment_id != new.deployment_id {
+        log::warn!("The builds belong to different deployments");
+    }
+
+    let old_digest = image_digest(&old)?;
+    let new_digest = image_digest(&new)?;
+
+    println!("{} {} {old_digest}", style("-").red(), format_build(&old));
+    println!("{} {} {new_digest}", style("+").green(), format_build(&new));
+
+    if old_digest == new_digest {
+        log::info!("Both builds produced the same image");
+
+        return Ok(());
+    }
+
+    let old_image = build_image_ref(
+        &get_deployment(&state.http, &old.deployment_id)
+            .await?
+            .config
+            .image
+            .name,
+        old_digest,
+    );
+
+    let new_image = build_image_ref(
+        &get_deployment(&state.http, &new.deployment_id)
+            .await?
+            .config
+            .image
+            .name,
+        new_digest,
+    );
+
+    log::info!("Pulling `{old_image}`");
+    let engine = pull_image(&state, &old_image).await?;
+
+    log::info!("Pulling `{new_image}`");
+    pull_image(&state, &new_image).await?;
+
+    let old_inspect = inspect_image(engine, &old_image).await?;
+    let new_inspect = inspect_image(engine, &new_image).await?;
+
+    println!();
+    println!(
+        "Size: {} -> {} ({})",
+        user_friendly_size(old_inspect.size)?,
+        user_friendly_size(new_inspect.size)?,
+        format_size_change(old_inspect.size, new_inspect.size)?
+    );
+
+    let old_layers = image_layers(engine, &old_image, &old_inspect).await?;
+    let new_layers = image_layers(engine, &new_image, &new_inspect).await?;
+
+    let shared = shared_layers(&old_layers, &new_layers);
+
+    println!();
+    println!(
+        "Layers: {shared} shared, {} removed, {} added",
+        old_layers.len() - shared,
+        new_layers.len() - shared
+    );
+
+    for layer in &old_layers[shared..] {
+        println!("  {} {}", style("-").red(), format_layer(layer)?);
+    }
+
+    for layer in &new_layers[shared..] {
+        println!("  {} {}", style("+").green(), format_layer(layer)?);
+    }
+
+    if options.no_files {
+        return Ok(());
+    }
+
+    log::info!("Comparing the files of both images");
+
+    let changes = diff_files(
+        &image_files(engine, &old_image).await?,
+        &image_files(engine, &new_image).await?,
+    );
+
+    let count = |filter: fn(&FileChange) -> bool| changes.iter().filter(|c| filter(c)).count();
+
+    println!();
+    println!(
+        "Files: {} added, {} removed, {} changed",
+        count(|c| matches!(c, FileChange::Added { .. })),
+        count(|c| matches!(c, FileChange::Removed { .. })),
+        count(|c| matches!(c, FileChange::Changed { .. }))
+    );
+
+    let shown = if options.all_files {
+        changes.len()
+    } else {
+        FILES_LIMIT
+    };
+
+    for change in changes.iter().take(shown) {
+        println!("  {}", format_file_change(change)?);
+    }
+
+    if changes.len() > shown {
+        println!(
+            "  ... and {} more, use `--all-files` to list them",
+            changes.len() - shown
+        );
+    }
+
+    Ok(())
+}
+
+fn image_digest(build: &Build) -> Result<&str> {
+    match &build.digest {
+        Some(digest) => Ok(digest),
+        None => bail!("Build `{}` has no image, it is {}", build.id, build.state),
+    }
+}
+
+fn format_build(build: &Build) -> String {
+    format!(
+        "{} ({}, {})",
+        build.id,
+        build.state,
+        relative_time(build.started_at)
+    )
+}
+
+fn format_layer(layer: &ImageLayer) -> Result<String> {
+    let size = match layer.size {
+        Some(size) => user_friendly_size(size)?,
+        None => "-".to_string(),
+    };
+
+    let description = layer.created_by.clone().unwrap_or_else(|| {
+        // digests are long, the start is enough to tell them apart
+        layer.digest.chars().take(19).collect()
+    });
+
+    Ok(format!("{size:>10}  {description}"))
+}
+
+fn format_file_change(change: &FileChange) -> Result<String> {
+    Ok(match change {
+        FileChange::Added { path, size } => format!(
+            "{} {path} ({})",
+            style("+").green(),
+            user_friendly_size(*size)?
+        ),
+
+        FileChange::Removed { path, size } => format!(
+            "{} {path} ({})",
+            style("-").red(),
+            user_friendly_size(*size)?
+        ),
+
+        FileChange::Changed { path, old, new } => format!(
+            "{} {path} ({})",
+            style("~").yellow(),
+            format_size_change(*old, *new)?
+        ),
+    })
+}
+
+fn format_size_change(old: u64, new: u64) -> Result<String> {
+    let sign = if new >= old { "+" } else { "-" };
+
+    Ok(format!("{sign}{}", user_friendly_size(old.abs_diff(new))?))
+}
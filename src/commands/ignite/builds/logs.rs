@@ -0,0 +1,107 @@
+use anyhow::{ensure, Result};
+use clap::Parser;
+
+use super::types::BuildState;
+use super::utils::{
+    follow_build, format_build_logs, format_builds, get_all_builds, get_build, get_build_logs,
+    get_deployment_project,
+};
+use crate::commands::deploy::connect_to_leap;
+use crate::commands::ignite::groups::utils::fetch_grouped_deployments;
+use crate::state::State;
+use crate::utils::open_in_pager;
+
+#[derive(Debug, Parser)]
+#[clap(about = "Get the logs of a build, following it if it is still running")]
+#[group(skip)]
+pub struct Options {
+    #[clap(help = "ID of the build")]
+    pub build: Option<String>,
+
+    #[clap(long, help = "Only show the logs so far of a running build")]
+    pub no_follow: bool,
+
+    #[clap(
+        short,
+        long,
+        help = "Show timestamps, only for logs that were already stored"
+    )]
+    pub timestamps: bool,
+}
+
+pub async fn handle(options: Options, state: State) -> Result<()> {
+    let build_id = match options.build {
+        Some(id) => id,
+
+        None => {
+            let (deployments_fmt, deployments, validator) =
+                fetch_grouped_deployments(&state, false, true).await?;
+
+            let idx = loop {
+                let idx = dialoguer::Select::new()
+                    .with_prompt("Select a deployment")
+                    .items(&deployments_fmt)
+                    .default(0)
+                    .interact()?;
+
+                if let Ok(idx) = validator(idx) {
+                    break idx;
+                }
+
+                console::Term::stderr().clear_last_lines(1)?
+            };
+
+            let builds = get_all_builds(&state.http, &deployments[idx].id).await?;
+            ensure!(!builds.is_empty(), "No builds found");
+            let builds_fmt = format_builds(&builds, false);
+
+            let idx = dialoguer::Select::new()
+                .with_prompt("Select a build")
+                .items(&builds_fmt)
+                .default(0)
+                .interact()?;
+
+            builds[idx].id.clone()
+        }
+    };
+
+    let build = get_build(&state.http, &build_id).await?;
+
+    if matches!(build.state, BuildState::Pending) && !options.no_follow {
+        let project = get_deployment_project(&state, &build.deployment_id).await?;
+
+        let mut leap = connect_to_leap(&state, &project.id).await?;
+
+        // the build could have finished while connecting, then there is nothing to follow
+        let build = get_build(&state.http, &build_id).await?;
+
+        if matches!(build.state, BuildState::Pending) {
+            let result = follow_build(
+                &state.http,
+                &mut leap,
+                &project.id,
+                &build,
+                options.timestamps,
+            )
+            .await;
+
+            leap.close().await;
+
+            return result;
+        }
+
+        leap.close().await;
+    }
+
+    let logs = get_build_logs(&state.http, &build.id).await?;
+
+    ensure!(!logs.is_empty(), "No logs found for build `{}`", build.id);
+
+    log::info!("Build `{}` is {}", build.id, build.state);
+
+    open_in_pager(
+        &format!("ignite_build_logs-{}", build.id),
+        &format_build_logs(&logs, options.timestamps),
+    )
+    .await
+}
@@ -1,7 +1,10 @@
 mod cancel;
+mod diff;
 mod list;
+mod logs;
 pub mod types;
 pub mod utils;
+mod watch;
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
@@ -14,6 +17,9 @@ pub enum Commands {
     List(list::Options),
     #[clap(alias = "stop")]
     Cancel(cancel::Options),
+    Logs(logs::Options),
+    Watch(watch::Options),
+    Diff(diff::Options),
 }
 
 #[derive(Debug, Parser)]
@@ -28,5 +34,8 @@ pub async fn handle(options: Options, state: State) -> Result<()> {
     match options.commands {
         Commands::List(options) => list::handle(options, state).await,
         Commands::Cancel(options) => cancel::handle(options, state).await,
+        Commands::Logs(options) => logs::handle(options, state).await,
+        Commands::Watch(options) => watch::handle(options, state).await,
+        Commands::Diff(options) => diff::handle(options, state).await,
     }
 }
@@ -8,6 +8,11 @@ pub struct MultipleBuilds {
     pub builds: Vec<Build>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct SingleBuild {
+    pub build: Build,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum BuildMethod {
@@ -53,4 +58,73 @@ pub struct Build {
     pub state: BuildState,
     pub digest: Option<String>,
     pub finished_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub metadata: Option<BuildMetadata>,
+}
+
+/// Set by the CLI when uploading the build context
+#[derive(Debug, Deserialize, Serialize, Default)]
+pub struct BuildMetadata {
+    /// Hash of the packed build context, used to skip builds of unchanged contexts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+}
+
+/// Output of the builder, the same chunks that are sent live as build progress
+#[derive(Debug, Deserialize)]
+pub struct BuildLog {
+    pub id: String,
+    pub log: String,
+    pub sent_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BuildLogs {
+    pub logs: Vec<BuildLog>,
+}
+
+/// Parts of `docker image inspect` needed to compare images
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ImageInspect {
+    pub size: u64,
+    #[serde(rename = "RootFS")]
+    pub root_fs: RootFs,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct RootFs {
+    #[serde(default)]
+    pub layers: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageLayer {
+    pub digest: String,
+    pub size: Option<u64>,
+    pub created_by: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageFile {
+    pub size: u64,
+    pub hash: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum FileChange {
+    Added { path: String, size: u64 },
+    Removed { path: String, size: u64 },
+    Changed { path: String, old: u64, new: u64 },
+}
+
+impl FileChange {
+    pub fn path(&self) -> &str {
+        match self {
+            Self::Added { path, .. } | Self::Removed { path, .. } | Self::Changed { path, .. } => {
+                path
+            }
+        }
+    }
 }
@@ -1,11 +1,27 @@
+use std::collections::{HashMap, HashSet};
 use std::io::Write;
+use std::process::Stdio;
 
-use anyhow::Result;
+use anyhow::{bail, ensure, Context, Result};
+use futures_util::StreamExt;
+use leap_client_rs::LeapEdge;
 use ms::{__to_string__, ms};
 use serde_json::Value;
+use sha1::{Digest, Sha1};
+use tokio::io::AsyncReadExt;
+use tokio_tar::Archive;
 
-use super::types::{Build, MultipleBuilds};
+use super::types::{
+    Build, BuildLog, BuildLogs, FileChange, ImageFile, ImageInspect, ImageLayer, MultipleBuilds,
+    SingleBuild,
+};
+use crate::commands::deploy::builder::watch;
+use crate::commands::deploy::local::IGNITE_PLATFORM;
+use crate::commands::ignite::utils::get_all_deployments;
+use crate::commands::projects::types::Project;
 use crate::state::http::HttpClient;
+use crate::state::State;
+use crate::utils::engine::ContainerEngine;
 use crate::utils::relative_time;
 
 pub async fn get_all_builds(http: &HttpClient, deployment_id: &str) -> Result<Vec<Build>> {
@@ -25,6 +41,26 @@ pub async fn get_all_builds(http: &HttpClient, deployment_id: &str) -> Result<Ve
     Ok(response.builds)
 }
 
+pub async fn get_build(http: &HttpClient, build_id: &str) -> Result<Build> {
+    let response = http
+        .request::<SingleBuild>("GET", &format!("/ignite/builds/{build_id}"), None)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Could not parse response"))?;
+
+    Ok(response.build)
+}
+
+pub async fn get_build_logs(http: &HttpClient, build_id: &str) -> Result<Vec<BuildLog>> {
+    let mut response = http
+        .request::<BuildLogs>("GET", &format!("/ignite/builds/{build_id}/logs"), None)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Could not parse response"))?;
+
+    response.logs.sort_by_key(|log| log.sent_at);
+
+    Ok(response.logs)
+}
+
 pub async fn cancel_build(http: &HttpClient, build_id: &str) -> Result<()> {
     http.request::<Value>("POST", &format!("/ignite/builds/{build_id}/cancel"), None)
         .await?;
@@ -65,3 +101,436 @@ pub fn format_builds(builds: &[Build], title: bool) -> Vec<String> {
         .map(std::string::ToString::to_string)
         .collect()
 }
+
+/// Prints the logs of a running build so far and follows it until it is pushed,
+/// leap has to be connected before checking that the build is still running
+/// Build events are sent to the project of the deployment, which does not have to be the
+/// current one, so the current project is checked first and then every other one
+pub async fn get_deployment_project(state: &State, deployment_id: &str) -> Result<Project> {
+    let current = state.ctx.current_project();
+
+    let mut projects = state
+        .ctx
+        .current
+        .as_ref()
+        .map(|user| user.projects.clone())
+        .unwrap_or_default();
+
+    projects.sort_by_key(|project| Some(&project.id) != current.as_ref().map(|p| &p.id));
+
+    for project in projects {
+        if get_all_deployments(&state.http, &project.id)
+            .await?
+            .iter()
+            .any(|deployment| deployment.id == deployment_id)
+        {
+            return Ok(project);
+        }
+    }
+
+    bail!("Could not find the project of deployment `{deployment_id}`")
+}
+
+pub async fn follow_build(
+    http: &HttpClient,
+    leap: &mut LeapEdge,
+    project_id: &str,
+    build: &Build,
+    timestamps: bool,
+) -> Result<()> {
+    let logs = get_build_logs(http, &build.id).await?;
+
+    print!("{}", format_build_logs(&logs, timestamps));
+
+    let seen = logs.into_iter().map(|log| log.id).collect::<HashSet<_>>();
+
+    watch(
+        leap,
+        project_id,
+        &build.deployment_id,
+        &build.id,
+        None,
+        &seen,
+    )
+    .await
+}
+
+/// Joins the output chunks of a build, optionally prefixing every line with its timestamp
+pub fn format_build_logs(logs: &[BuildLog], timestamps: bool) -> String {
+    if !timestamps {
+        return logs.iter().map(|log| log.log.as_str()).collect();
+    }
+
+    logs.iter()
+        .flat_map(|log| {
+            log.log
+                .lines()
+                .map(move |line| format!("{} {line}\n", log.sent_at.format("%F %T")))
+        })
+        .collect()
+}
+
+/// Image of a build, pinned to the digest it was pushed with
+pub fn build_image_ref(image: &str, digest: &str) -> String {
+    // a tag next to the digest would be ignored at best
+    let name = match image.rsplit_once(':') {
+        Some((name, tag)) if !tag.contains('/') => name,
+        _ => image,
+    };
+
+    format!("{name}@{digest}")
+}
+
+pub async fn inspect_image(engine: ContainerEngine, image: &str) -> Result<ImageInspect> {
+    let output = engine
+        .command()
+        .arg("image")
+        .arg("inspect")
+        .arg("--format={{json .}}")
+        .arg(image)
+        .output()
+        .await?;
+
+    ensure!(
+        output.status.success(),
+        "Failed to inspect `{image}`: {}",
+        String::from_utf8_lossy(&output.stderr).trim()
+    );
+
+    serde_json::from_slice(&output.stdout).context("Could not parse the image details")
+}
+
+/// Layers of the image from the bottom up, with the steps that created them if the
+/// history lines up with the layers
+pub async fn image_layers(
+    engine: ContainerEngine,
+    image: &str,
+    inspect: &ImageInspect,
+) -> Result<Vec<ImageLayer>> {
+    let output = engine
+        .command()
+        .arg("history")
+        .arg("--no-trunc")
+        .arg("--human=false")
+        .arg("--format={{.Size}}\t{{.CreatedBy}}")
+        .arg(image)
+        .output()
+        .await?;
+
+    let steps = if output.status.success() {
+        parse_history(&String::from_utf8_lossy(&output.stdout))
+    } else {
+        log::debug!(
+            "Failed to get the history of `{image}`: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+
+        vec![]
+    };
+
+    Ok(match_layers(&inspect.root_fs.layers, steps))
+}
+
+/// Steps of the history that added a layer, oldest first
+fn parse_history(output: &str) -> Vec<(u64, String)> {
+    output
+        .lines()
+        .rev()
+        .filter_map(|line| {
+            let (size, created_by) = line.split_once('\t')?;
+            let size = size.trim().parse::<u64>().ok()?;
+
+            let created_by = created_by
+                .trim()
+                .trim_start_matches("/bin/sh -c ")
+                .trim_start_matches("#(nop) ")
+                .trim()
+                .to_string();
+
+            (size > 0).then_some((size, created_by))
+        })
+        .collect()
+}
+
+fn match_layers(layers: &[String], steps: Vec<(u64, String)>) -> Vec<ImageLayer> {
+    // empty layers have no size in the history, so only a full match is trusted
+    let steps = if steps.len() == layers.len() {
+        steps.into_iter().map(Some).collect()
+    } else {
+        vec![None; layers.len()]
+    };
+
+    layers
+        .iter()
+        .zip(steps)
+        .map(|(digest, step)| ImageLayer {
+            digest: digest.clone(),
+            size: step.as_ref().map(|(size, _)| *size),
+            created_by: step.map(|(_, created_by)| created_by),
+        })
+        .collect()
+}
+
+/// Number of layers both images share from the bottom up
+pub fn shared_layers(a: &[ImageLayer], b: &[ImageLayer]) -> usize {
+    a.iter()
+        .zip(b)
+        .take_while(|(a, b)| a.digest == b.digest)
+        .count()
+}
+
+/// Regular files of the image with their size and content hash
+pub async fn image_files(
+    engine: ContainerEngine,
+    image: &str,
+) -> Result<HashMap<String, ImageFile>> {
+    // the command is never run, it only keeps images without one valid
+    let output = engine
+        .command()
+        .arg("create")
+        .arg(format!("--platform={IGNITE_PLATFORM}"))
+        .arg(image)
+        .arg("true")
+        .output()
+        .await?;
+
+    ensure!(
+        output.status.success(),
+        "Failed to create a container from `{image}`: {}",
+        String::from_utf8_lossy(&output.stderr).trim()
+    );
+
+    let container = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    let files = export_files(engine, &container).await;
+
+    engine
+        .command()
+        .arg("rm")
+        .arg(&container)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await?;
+
+    files
+}
+
+async fn export_files(
+    engine: ContainerEngine,
+    container: &str,
+) -> Result<HashMap<String, ImageFile>> {
+    let mut child = engine
+        .command()
+        .arg("export")
+        .arg(container)
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    let stdout = child
+        .stdout
+        .take()
+        .context("Could not read the exported container")?;
+
+    let mut archive = Archive::new(stdout);
+    let mut entries = archive.entries()?;
+
+    let mut files = HashMap::new();
+    let mut buffer = vec![0; 64 * 1024];
+
+    while let Some(entry) = entries.next().await {
+        let mut entry = entry?;
+
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+
+        let path = entry.path()?.to_string_lossy().to_string();
+
+        let mut hasher = Sha1::new();
+        let mut size = 0;
+
+        loop {
+            let read = entry.read(&mut buffer).await?;
+
+            if read == 0 {
+                break;
+            }
+
+            hasher.update(&buffer[..read]);
+            size += read as u64;
+        }
+
+        files.insert(
+            path,
+            ImageFile {
+                size,
+                hash: format!("{:x}", hasher.finalize()),
+            },
+        );
+    }
+
+    ensure!(
+        child.wait().await?.success(),
+        "Failed to export container `{container}`"
+    );
+
+    Ok(files)
+}
+
+pub fn diff_files(
+    a: &HashMap<String, ImageFile>,
+    b: &HashMap<String, ImageFile>,
+) -> Vec<FileChange> {
+    let mut changes = vec![];
+
+    for (path, old) in a {
+        match b.get(path) {
+            None => changes.push(FileChange::Removed {
+                path: path.clone(),
+                size: old.size,
+            }),
+
+            Some(new) if new.hash != old.hash => changes.push(FileChange::Changed {
+                path: path.clone(),
+                old: old.size,
+                new: new.size,
+            }),
+
+            Some(_) => {}
+        }
+    }
+
+    for (path, new) in b {
+        if !a.contains_key(path) {
+            changes.push(FileChange::Added {
+                path: path.clone(),
+                size: new.size,
+            });
+        }
+    }
+
+    changes.sort_by(|a, b| a.path().cmp(b.path()));
+
+    changes
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_format_build_logs() {
+        let log = |id: &str, log: &str, secs: i64| BuildLog {
+            id: id.to_string(),
+            log: log.to_string(),
+            sent_at: chrono::DateTime::from_timestamp(secs, 0).unwrap(),
+        };
+
+        let logs = vec![log("1", "step 1\nstep ", 0), log("2", "2\n", 61)];
+
+        assert_eq!(format_build_logs(&logs, false), "step 1\nstep 2\n");
+        assert_eq!(
+            format_build_logs(&logs, true),
+            "1970-01-01 00:00:00 step 1\n1970-01-01 00:00:00 step \n1970-01-01 00:01:01 2\n"
+        );
+    }
+
+    #[test]
+    fn test_build_image_ref() {
+        assert_eq!(
+            build_image_ref("registry.hop.io/ns/app", "sha256:abc"),
+            "registry.hop.io/ns/app@sha256:abc"
+        );
+        assert_eq!(
+            build_image_ref("registry.hop.io/ns/app:latest", "sha256:abc"),
+            "registry.hop.io/ns/app@sha256:abc"
+        );
+        assert_eq!(
+            build_image_ref("localhost:5000/app", "sha256:abc"),
+            "localhost:5000/app@sha256:abc"
+        );
+    }
+
+    #[test]
+    fn test_match_layers() {
+        let history = "0\t/bin/sh -c #(nop)  CMD [\"node\"]\n20\tCOPY . /app\n0\tWORKDIR /app\n100\t/bin/sh -c #(nop) ADD file:abc in /\n";
+
+        let steps = parse_history(history);
+
+        assert_eq!(
+            steps,
+            vec![
+                (100, "ADD file:abc in /".to_string()),
+                (20, "COPY . /app".to_string())
+            ]
+        );
+
+        let layers = match_layers(&["a".to_string(), "b".to_string()], steps.clone());
+
+        assert_eq!(layers[1].size, Some(20));
+        assert_eq!(layers[1].created_by.as_deref(), Some("COPY . /app"));
+
+        // a layer without a history entry makes the steps unusable
+        let layers = match_layers(&["a".to_string(), "b".to_string(), "c".to_string()], steps);
+
+        assert!(layers.iter().all(|layer| layer.size.is_none()));
+    }
+
+    #[test]
+    fn test_shared_layers() {
+        let layer = |digest: &str| ImageLayer {
+            digest: digest.to_string(),
+            size: None,
+            created_by: None,
+        };
+
+        assert_eq!(
+            shared_layers(
+                &[layer("a"), layer("b"), layer("c")],
+                &[layer("a"), layer("b"), layer("d"), layer("e")]
+            ),
+            2
+        );
+        assert_eq!(shared_layers(&[layer("a")], &[layer("b")]), 0);
+    }
+
+    #[test]
+    fn test_diff_files() {
+        let file = |size: u64, hash: &str| ImageFile {
+            size,
+            hash: hash.to_string(),
+        };
+
+        let a = HashMap::from([
+            ("app/index.js".to_string(), file(10, "1")),
+            ("app/old.js".to_string(), file(5, "2")),
+            ("etc/hosts".to_string(), file(1, "3")),
+        ]);
+
+        let b = HashMap::from([
+            ("app/index.js".to_string(), file(12, "4")),
+            ("app/new.js".to_string(), file(7, "5")),
+            ("etc/hosts".to_string(), file(1, "3")),
+        ]);
+
+        assert_eq!(
+            diff_files(&a, &b),
+            vec![
+                FileChange::Changed {
+                    path: "app/index.js".to_string(),
+                    old: 10,
+                    new: 12
+                },
+                FileChange::Added {
+                    path: "app/new.js".to_string(),
+                    size: 7
+                },
+                FileChange::Removed {
+                    path: "app/old.js".to_string(),
+                    size: 5
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,169 @@
+use std::time::Duration;
+
+use anyhow::{bail, ensure, Result};
+use clap::Parser;
+use ms::{__to_string__, ms};
+use tokio::time::timeout;
+
+use super::types::BuildState;
+use super::utils::{
+    follow_build, format_builds, get_all_builds, get_build, get_deployment_project,
+};
+use crate::commands::deploy::{builder, connect_to_leap, rollout_with_checks, RolloutOptions};
+use crate::commands::ignite::groups::utils::fetch_grouped_deployments;
+use crate::commands::ignite::utils::get_deployment;
+use crate::state::State;
+use crate::utils::parse_duration;
+
+#[derive(Debug, Parser)]
+#[clap(about = "Reattach to a build and roll it out once it is pushed")]
+#[group(skip)]
+pub struct Options {
+    #[clap(help = "ID of the build")]
+    pub build: Option<String>,
+
+    #[clap(long, help = "Do not roll out the build after it is pushed")]
+    pub no_rollout: bool,
+
+    #[clap(
+        long,
+        help = "How long to wait for the build to be pushed",
+        default_value = "30m",
+        value_parser = parse_duration
+    )]
+    pub build_timeout: Duration,
+
+    #[clap(flatten)]
+    pub rollout: RolloutOptions,
+}
+
+pub async fn handle(options: Options, state: State) -> Result<()> {
+    ensure!(
+        !options.no_rollout || !(options.rollout.wait_healthy || options.rollout.auto_rollback),
+        "`--wait-healthy` and `--auto-rollback` can not be used with `--no-rollout`"
+    );
+
+    let build_id = match options.build {
+        Some(id) => id,
+
+        None => {
+            let (deployments_fmt, deployments, validator) =
+                fetch_grouped_deployments(&state, false, true).await?;
+
+            let idx = loop {
+                let idx = dialoguer::Select::new()
+                    .with_prompt("Select a deployment")
+                    .items(&deployments_fmt)
+                    .default(0)
+                    .interact()?;
+
+                if let Ok(idx) = validator(idx) {
+                    break idx;
+                }
+
+                console::Term::stderr().clear_last_lines(1)?
+            };
+
+            let builds = get_all_builds(&state.http, &deployments[idx].id)
+                .await?
+                .into_iter()
+                .filter(|b| matches!(b.state, BuildState::Pending))
+                .collect::<Vec<_>>();
+            ensure!(!builds.is_empty(), "No running builds found");
+            let builds_fmt = format_builds(&builds, false);
+
+            let idx = dialoguer::Select::new()
+                .with_prompt("Select a build")
+                .items(&builds_fmt)
+                .default(0)
+                .interact()?;
+
+            builds[idx].id.clone()
+        }
+    };
+
+    let build = get_build(&state.http, &build_id).await?;
+    let deployment = get_deployment(&state.http, &build.deployment_id).await?;
+    let project = get_deployment_project(&state, &deployment.id).await?;
+
+    // the build running before this one, to roll back to
+    let previous_build = get_all_builds(&state.http, &deployment.id)
+        .await?
+        .into_iter()
+        .find(|b| matches!(b.state, BuildState::Succeeded) && b.id != build.id)
+        .map(|b| b.id);
+
+    // connect before checking the state again so the push can not be missed
+    let mut leap = connect_to_leap(&state, &project.id).await?;
+
+    let build = get_build(&state.http, &build_id).await?;
+
+    match build.state {
+        BuildState::Pending => {
+            log::info!("Attached to build `{}` of `{}`", build.id, deployment.name);
+
+            let result = timeout(
+                options.build_timeout,
+                follow_build(&state.http, &mut leap, &project.id, &build, false),
+            )
+            .await;
+
+            match result {
+                Ok(Ok(())) => {}
+
+                Ok(Err(error)) => {
+                    leap.close().await;
+
+                    return Err(error);
+                }
+
+                Err(_) => {
+                    leap.close().await;
+
+                    bail!(
+                        "Build `{}` was not pushed within {}, it keeps running, check it with `hop ignite builds logs {}`",
+                        build.id,
+                        ms!(options.build_timeout.as_millis() as u64, true),
+                        build.id
+                    );
+                }
+            }
+        }
+
+        BuildState::Succeeded => log::info!("Build `{}` was already pushed", build.id),
+
+        state => {
+            leap.close().await;
+
+            bail!("Build `{}` is {state}, nothing to roll out", build.id);
+        }
+    }
+
+    if let Err(error) = builder::confirm_pending_build(&deployment.id, &build.id).await {
+        log::warn!("Could not remember the build for unchanged checks: {error}");
+    }
+
+    if !options.no_rollout && deployment.can_rollout() {
+        log::info!("Rolling out `{}`", deployment.name);
+
+        let result = rollout_with_checks(
+            &state,
+            &mut leap,
+            &project.id,
+            &deployment.id,
+            &options.rollout,
+            previous_build.as_deref(),
+        )
+        .await;
+
+        leap.close().await;
+
+        result?;
+
+        log::info!("Rolled out build `{}`", build.id);
+    } else {
+        leap.close().await;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,228 @@
+pub mod utils;
+
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+
+use self::utils::{create_clone, get_clone_parts};
+use super::types::CreateDeployment;
+use super::utils::{get_all_deployments, rewrite_registry_image};
+use crate::commands::containers::utils::create_containers;
+use crate::commands::ignite::health::utils::wait_for_healthy;
+use crate::commands::projects::utils::format_project;
+use crate::commands::volumes::copy::fslike::FsLike;
+use crate::commands::volumes::utils::get_volume_from_deployment;
+use crate::state::State;
+
+/// How long to wait for the new container before copying the volume
+const VOLUME_COPY_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug, Parser)]
+#[clap(about = "Clone a deployment, optionally into another project")]
+#[group(skip)]
+pub struct Options {
+    #[clap(help = "Name or ID of the deployment to clone")]
+    pub deployment: Option<String>,
+
+    #[clap(
+        short,
+        long,
+        help = "Name of the new deployment, defaults to <name>-clone"
+    )]
+    pub name: Option<String>,
+
+    #[clap(
+        long,
+        help = "Namespace or ID of the project to clone into, defaults to the current project"
+    )]
+    pub target_project: Option<String>,
+
+    #[clap(long, help = "Copy the contents of the volume to the new deployment")]
+    pub copy_volume: bool,
+}
+
+pub async fn handle(options: Options, state: State) -> Result<()> {
+    let source_project = state.ctx.current_project_error()?;
+
+    let target_project = match options.target_project {
+        Some(project) => state
+            .ctx
+            .find_project_by_id_or_namespace(&project)
+            .with_context(|| format!("Could not find project `{project}`"))?,
+        None => source_project.clone(),
+    };
+
+    let source = state
+        .get_deployment_by_opt_name_or_id(options.deployment.as_deref())
+        .await?;
+
+    let name = options
+        .name
+        .unwrap_or_else(|| format!("{}-clone", source.name));
+
+    if get_all_deployments(&state.http, &target_project.id)
+        .await?
+        .iter()
+        .any(|deployment| deployment.name == name)
+    {
+        bail!(
+            "Deployment `{name}` already exists in project `{}`",
+            target_project.namespace
+        );
+    }
+
+    if options.copy_volume && !source.is_stateful() {
+        bail!("Deployment `{}` does not have a volume", source.name);
+    }
+
+    let mut config = CreateDeployment::from(source.clone());
+    config.name = Some(name.clone());
+
+    // images pushed to the Hop registry are scoped to the project namespace
+    let mut needs_image = false;
+
+    if let Some(image) = config.image.as_mut() {
+        let rewritten = rewrite_registry_image(
+            &image.name,
+            &source_project.namespace,
+            &target_project.namespace,
+        );
+
+        needs_image = rewritten != image.name;
+        image.name = rewritten;
+    }
+
+    // the volume is written through a running container of the clone
+    if options.copy_volume && needs_image {
+        bail!(
+            "The image of `{}` has to be deployed to `{}` before its volume can be copied, clone it without `--copy-volume`",
+            source.name,
+            target_project.namespace
+        );
+    }
+
+    if options.copy_volume && source.target_container_count == 0 {
+        bail!(
+            "Deployment `{}` has no containers to copy the volume into, scale it up first",
+            source.name
+        );
+    }
+
+    log::info!(
+        "Cloning `{}` into project {}",
+        source.name,
+        format_project(&target_project)
+    );
+
+    let parts = get_clone_parts(&state.http, &source, &name).await?;
+
+    let deployment = create_clone(&state.http, &target_project.id, &config, &parts).await?;
+
+    if let Some(group_id) = &source.group_id {
+        let group_id = if target_project.id == source_project.id {
+            Some(group_id.clone())
+        } else {
+            clone_group(&state, &source_project.id, &target_project.id, group_id).await?
+        };
+
+        if let Some(group_id) = group_id {
+            state
+                .hop
+                .ignite
+                .groups
+                .move_deployment(Some(&group_id), &deployment.id)
+                .await?;
+        }
+    }
+
+    if needs_image {
+        log::warn!(
+            "The image of `{}` lives in the registry of `{}`, deploy it again with `hop deploy`",
+            source.name,
+            source_project.namespace
+        );
+    } else if source.target_container_count > 0 {
+        create_containers(&state.http, &deployment.id, source.target_container_count).await?;
+    }
+
+    if options.copy_volume {
+        wait_for_healthy(&state.http, &deployment.id, |_| true, VOLUME_COPY_TIMEOUT).await?;
+
+        log::info!("Copying volume contents");
+
+        let source_volume = FsLike::new_remote(
+            &state.http,
+            &source.id,
+            &get_volume_from_deployment(&source.id)?,
+            "/",
+        );
+
+        let (packed, data) = source_volume.read().await?;
+
+        FsLike::new_remote(
+            &state.http,
+            &deployment.id,
+            &get_volume_from_deployment(&deployment.id)?,
+            "/",
+        )
+        .write(data, packed)
+        .await?;
+    }
+
+    if !parts.domains.is_empty() {
+        log::warn!(
+            "Custom domains stay attached to `{}`, move them to `{}` manually if needed: {}",
+            source.name,
+            deployment.name,
+            parts.domains.join(", ")
+        );
+    }
+
+    log::info!("Cloned `{}` into `{}`", source.name, deployment.name);
+
+    Ok(())
+}
+
+/// Finds or creates a group with the same name in the target project
+async fn clone_group(
+    state: &State,
+    source_project_id: &str,
+    target_project_id: &str,
+    group_id: &str,
+) -> Result<Option<String>> {
+    let Some(group) = state
+        .hop
+        .ignite
+        .groups
+        .get_all(source_project_id)
+        .await?
+        .into_iter()
+        .find(|group| group.id == group_id)
+    else {
+        return Ok(None);
+    };
+
+    let existing = state
+        .hop
+        .ignite
+        .groups
+        .get_all(target_project_id)
+        .await?
+        .into_iter()
+        .find(|target| target.name == group.name);
+
+    let target = match existing {
+        Some(target) => target,
+        None => {
+            state
+                .hop
+                .ignite
+                .groups
+                .create(target_project_id, &group.name, &[])
+                .await?
+        }
+    };
+
+    Ok(Some(target.id))
+}
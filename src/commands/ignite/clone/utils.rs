@@ -0,0 +1,158 @@
+use anyhow::Result;
+
+use crate::commands::gateways::types::{Gateway, GatewayConfig, GatewayType};
+use crate::commands::gateways::util::{create_gateway, get_all_gateways};
+use crate::commands::ignite::health::types::CreateHealthCheck;
+use crate::commands::ignite::health::utils::{create_health_check, get_all_health_checks};
+use crate::commands::ignite::types::{CreateDeployment, Deployment};
+use crate::commands::ignite::utils::{create_deployment, delete_deployment};
+use crate::commands::projects::snapshot::utils::health_check_to_config;
+use crate::state::http::HttpClient;
+
+/// Gateways and health checks of a deployment, ready to be recreated on a clone
+#[derive(Debug, Default)]
+pub struct CloneParts {
+    pub gateways: Vec<GatewayConfig>,
+    pub health_checks: Vec<CreateHealthCheck>,
+    /// custom domains can only be attached to one gateway at a time, they are not copied
+    pub domains: Vec<String>,
+}
+
+/// Reads everything needed to clone `source` as `name`, before anything is created
+pub async fn get_clone_parts(
+    http: &HttpClient,
+    source: &Deployment,
+    name: &str,
+) -> Result<CloneParts> {
+    let (gateways, health_checks) = tokio::join!(
+        get_all_gateways(http, &source.id),
+        get_all_health_checks(http, &source.id)
+    );
+
+    let mut parts = CloneParts::default();
+
+    for gateway in gateways? {
+        parts
+            .gateways
+            .push(clone_gateway_config(&gateway, &source.name, name));
+
+        parts
+            .domains
+            .extend(gateway.domains.into_iter().map(|domain| domain.domain));
+    }
+
+    for health_check in health_checks? {
+        parts
+            .health_checks
+            .push(health_check_to_config(&health_check)?);
+    }
+
+    Ok(parts)
+}
+
+/// Internal domains are unique within a project, so the clone gets its own
+pub fn clone_gateway_config(gateway: &Gateway, source_name: &str, name: &str) -> GatewayConfig {
+    let mut config = GatewayConfig::from_gateway(gateway);
+
+    if gateway.type_ == GatewayType::Internal {
+        // `api.hop` becomes `api-clone.hop`, anything else is left to the api to pick
+        config.internal_domain = gateway
+            .internal_domain
+            .as_deref()
+            .and_then(|domain| domain.strip_prefix(source_name))
+            .filter(|rest| rest.starts_with(['.', '-']))
+            .map(|rest| format!("{name}{rest}"));
+    }
+
+    config
+}
+
+/// Creates the deployment with its gateways and health checks, the deployment is
+/// deleted again if any of them can't be created so no half built clone is left
+pub async fn create_clone(
+    http: &HttpClient,
+    project_id: &str,
+    config: &CreateDeployment,
+    parts: &CloneParts,
+) -> Result<Deployment> {
+    let deployment = create_deployment(http, project_id, config).await?;
+
+    log::info!(
+        "Deployment `{}` ({}) created",
+        deployment.name,
+        deployment.id
+    );
+
+    if let Err(error) = create_clone_parts(http, &deployment.id, parts).await {
+        if let Err(delete_error) = delete_deployment(http, &deployment.id).await {
+            log::warn!(
+                "Could not delete `{}` after the failed clone: {delete_error}",
+                deployment.name
+            );
+        }
+
+        return Err(error);
+    }
+
+    Ok(deployment)
+}
+
+async fn create_clone_parts(
+    http: &HttpClient,
+    deployment_id: &str,
+    parts: &CloneParts,
+) -> Result<()> {
+    for gateway in &parts.gateways {
+        create_gateway(http, deployment_id, gateway).await?;
+    }
+
+    for health_check in &parts.health_checks {
+        create_health_check(http, deployment_id, health_check.clone()).await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_clone_gateway_config() {
+        let internal = Gateway {
+            name: Some("private".to_string()),
+            internal_domain: Some("api.hop".to_string()),
+            target_port: Some(8080),
+            type_: GatewayType::Internal,
+            ..Default::default()
+        };
+
+        // the default clone is `<name>-clone` in the same project
+        let config = clone_gateway_config(&internal, "api", "api-clone");
+
+        assert_eq!(config.internal_domain.as_deref(), Some("api-clone.hop"));
+        assert_eq!(config.name.as_deref(), Some("private"));
+        assert_eq!(config.target_port, Some(8080));
+
+        let other = Gateway {
+            internal_domain: Some("apiary.hop".to_string()),
+            ..internal.clone()
+        };
+
+        assert_eq!(
+            clone_gateway_config(&other, "api", "api-clone").internal_domain,
+            None
+        );
+
+        let external = Gateway {
+            type_: GatewayType::External,
+            internal_domain: None,
+            ..internal
+        };
+
+        assert_eq!(
+            clone_gateway_config(&external, "api", "api-clone").type_,
+            Some(GatewayType::External)
+        );
+    }
+}
@@ -0,0 +1,100 @@
+pub mod types;
+pub mod utils;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Parser;
+
+use self::utils::{deployment_from_file, diff_fields, flatten_deployment, format_field_change};
+use crate::commands::projects::snapshot::utils::snapshot_deployment;
+use crate::state::State;
+
+#[derive(Debug, Parser)]
+#[clap(about = "Compare the config, gateways and health checks of two deployments")]
+#[group(skip)]
+pub struct Options {
+    #[clap(help = "Name or ID of the deployment")]
+    pub deployment: String,
+
+    #[clap(
+        help = "Name or ID of the deployment to compare with",
+        required_unless_present = "against",
+        conflicts_with = "against"
+    )]
+    pub other: Option<String>,
+
+    #[clap(
+        long,
+        help = "Compare with the deployment of the same name in a project snapshot, or a file with a single deployment"
+    )]
+    pub against: Option<PathBuf>,
+
+    #[clap(
+        long,
+        help = "Show the values of env variables instead of masking them"
+    )]
+    pub show_values: bool,
+}
+
+pub async fn handle(options: Options, state: State) -> Result<()> {
+    let project = state.ctx.current_project_error()?;
+
+    let group_names = state
+        .hop
+        .ignite
+        .groups
+        .get_all(&project.id)
+        .await?
+        .into_iter()
+        .map(|group| (group.id, group.name))
+        .collect::<HashMap<_, _>>();
+
+    let deployment = state
+        .get_deployment_by_name_or_id(&options.deployment)
+        .await?;
+    let old_label = format!("{} ({})", deployment.name, deployment.id);
+    let old = snapshot_deployment(&state, deployment, &group_names).await?;
+
+    let (new_label, new) = match options.other {
+        Some(other) => {
+            let deployment = state.get_deployment_by_name_or_id(&other).await?;
+
+            (
+                format!("{} ({})", deployment.name, deployment.id),
+                snapshot_deployment(&state, deployment, &group_names).await?,
+            )
+        }
+
+        None => {
+            let path = options.against.unwrap();
+
+            (
+                path.display().to_string(),
+                deployment_from_file(&path, &old.name).await?,
+            )
+        }
+    };
+
+    let changes = diff_fields(&flatten_deployment(&old)?, &flatten_deployment(&new)?);
+
+    if changes.is_empty() {
+        log::info!("No differences between `{old_label}` and `{new_label}`");
+
+        return Ok(());
+    }
+
+    println!("--- {old_label}");
+    println!("+++ {new_label}");
+
+    for change in &changes {
+        for line in format_field_change(change, options.show_values) {
+            println!("{line}");
+        }
+    }
+
+    log::info!("{} fields differ", changes.len());
+
+    Ok(())
+}
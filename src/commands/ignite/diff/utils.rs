@@ -0,0 +1,311 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use console::style;
+use serde_json::Value;
+use tokio::fs;
+
+use super::types::FieldChange;
+use crate::commands::projects::snapshot::types::{Snapshot, SnapshotDeployment};
+use crate::commands::secrets::utils::get_secret_name;
+
+pub const MASK: &str = "********";
+
+/// Reads a deployment from a project snapshot, or a file with a single snapshot deployment
+pub async fn deployment_from_file(path: &Path, name: &str) -> Result<SnapshotDeployment> {
+    let content = fs::read_to_string(path)
+        .await
+        .with_context(|| format!("Could not read {}", path.display()))?;
+
+    // yaml is a superset of json so both work
+    if let Ok(snapshot) = serde_yaml::from_str::<Snapshot>(&content) {
+        return snapshot
+            .deployments
+            .into_iter()
+            .find(|deployment| deployment.name == name)
+            .with_context(|| format!("Deployment `{name}` is not in {}", path.display()));
+    }
+
+    serde_yaml::from_str(&content).with_context(|| {
+        format!(
+            "{} is not a project snapshot or a deployment",
+            path.display()
+        )
+    })
+}
+
+/// Flattens the deployment into `path: value` pairs, the name is left out as it always differs
+pub fn flatten_deployment(deployment: &SnapshotDeployment) -> Result<BTreeMap<String, Value>> {
+    let mut value = serde_json::to_value(deployment)?;
+    let mut fields = BTreeMap::new();
+
+    if let Value::Object(map) = &mut value {
+        map.remove("name");
+
+        // the api does not return gateways and health checks in a stable order,
+        // so they are keyed by what identifies them instead of their position
+        for (name, key) in [
+            ("gateways", gateway_key as KeyFn),
+            ("health_checks", health_check_key),
+        ] {
+            if let Some(Value::Array(mut items)) = map.remove(name) {
+                items.sort_by_key(std::string::ToString::to_string);
+
+                flatten_keyed(&items, name, key, &mut fields);
+            }
+        }
+    }
+
+    flatten(&value, String::new(), &mut fields);
+
+    Ok(fields)
+}
+
+type KeyFn = fn(&Value) -> Option<String>;
+
+fn gateway_key(gateway: &Value) -> Option<String> {
+    gateway["name"].as_str().map(str::to_string)
+}
+
+fn health_check_key(health_check: &Value) -> Option<String> {
+    Some(format!(
+        "{}:{}{}",
+        health_check["protocol"].as_str()?,
+        health_check["port"].as_u64()?,
+        health_check["path"].as_str()?
+    ))
+}
+
+/// Items without a key, or with one that is already taken, fall back to their index
+fn flatten_keyed(items: &[Value], path: &str, key: KeyFn, fields: &mut BTreeMap<String, Value>) {
+    let mut seen = BTreeSet::new();
+
+    for (idx, item) in items.iter().enumerate() {
+        let key = key(item)
+            .filter(|key| seen.insert(key.clone()))
+            .unwrap_or_else(|| idx.to_string());
+
+        flatten(item, format!("{path}[{key}]"), fields);
+    }
+}
+
+fn flatten(value: &Value, path: String, fields: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+
+                flatten(value, path, fields);
+            }
+        }
+
+        Value::Array(items) => {
+            for (idx, item) in items.iter().enumerate() {
+                flatten(item, format!("{path}[{idx}]"), fields);
+            }
+        }
+
+        // unset and missing fields mean the same
+        Value::Null => {}
+
+        _ => {
+            fields.insert(path, value.clone());
+        }
+    }
+}
+
+pub fn diff_fields(
+    old: &BTreeMap<String, Value>,
+    new: &BTreeMap<String, Value>,
+) -> Vec<FieldChange> {
+    old.keys()
+        .chain(new.keys())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .filter_map(|path| match (old.get(path), new.get(path)) {
+            (Some(old), None) => Some(FieldChange::Removed {
+                path: path.clone(),
+                value: old.clone(),
+            }),
+
+            (None, Some(new)) => Some(FieldChange::Added {
+                path: path.clone(),
+                value: new.clone(),
+            }),
+
+            (Some(old), Some(new)) if old != new => Some(FieldChange::Changed {
+                path: path.clone(),
+                old: old.clone(),
+                new: new.clone(),
+            }),
+
+            _ => None,
+        })
+        .collect()
+}
+
+/// Env values are masked unless they reference a secret, which only exposes its name
+pub fn format_value(path: &str, value: &Value, show_values: bool) -> String {
+    match value {
+        Value::String(value)
+            if !show_values
+                && path.starts_with("config.env.")
+                && get_secret_name(value).is_none() =>
+        {
+            MASK.to_string()
+        }
+
+        Value::String(value) => value.clone(),
+
+        value => value.to_string(),
+    }
+}
+
+pub fn format_field_change(change: &FieldChange, show_values: bool) -> Vec<String> {
+    let path = change.path();
+
+    let line = |sign: &str, value: &Value| {
+        format!("{sign} {path}: {}", format_value(path, value, show_values))
+    };
+
+    match change {
+        FieldChange::Removed { value, .. } => vec![style(line("-", value)).red().to_string()],
+        FieldChange::Added { value, .. } => vec![style(line("+", value)).green().to_string()],
+        FieldChange::Changed { old, new, .. } => vec![
+            style(line("-", old)).red().to_string(),
+            style(line("+", new)).green().to_string(),
+        ],
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_flatten() {
+        let mut fields = BTreeMap::new();
+
+        flatten(
+            &json!({
+                "config": { "cmd": ["a", "b"], "volume": null },
+                "gateways": [{ "name": "web" }],
+            }),
+            String::new(),
+            &mut fields,
+        );
+
+        assert_eq!(
+            fields,
+            BTreeMap::from([
+                ("config.cmd[0]".to_string(), json!("a")),
+                ("config.cmd[1]".to_string(), json!("b")),
+                ("gateways[0].name".to_string(), json!("web")),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_flatten_keyed() {
+        let mut fields = BTreeMap::new();
+
+        flatten_keyed(
+            &[
+                json!({ "name": "web", "target_port": 80 }),
+                json!({ "target_port": 81 }),
+            ],
+            "gateways",
+            gateway_key,
+            &mut fields,
+        );
+        flatten_keyed(
+            &[json!({ "protocol": "http", "port": 8080, "path": "/health", "interval": 5 })],
+            "health_checks",
+            health_check_key,
+            &mut fields,
+        );
+
+        assert_eq!(
+            fields,
+            BTreeMap::from([
+                ("gateways[1].target_port".to_string(), json!(81)),
+                ("gateways[web].name".to_string(), json!("web")),
+                ("gateways[web].target_port".to_string(), json!(80)),
+                (
+                    "health_checks[http:8080/health].interval".to_string(),
+                    json!(5)
+                ),
+                (
+                    "health_checks[http:8080/health].path".to_string(),
+                    json!("/health")
+                ),
+                (
+                    "health_checks[http:8080/health].port".to_string(),
+                    json!(8080)
+                ),
+                (
+                    "health_checks[http:8080/health].protocol".to_string(),
+                    json!("http")
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_diff_fields() {
+        let old = BTreeMap::from([
+            ("a".to_string(), json!(1)),
+            ("b".to_string(), json!(1)),
+            ("c".to_string(), json!(1)),
+        ]);
+        let new = BTreeMap::from([
+            ("b".to_string(), json!(2)),
+            ("c".to_string(), json!(1)),
+            ("d".to_string(), json!(1)),
+        ]);
+
+        assert_eq!(
+            diff_fields(&old, &new),
+            vec![
+                FieldChange::Removed {
+                    path: "a".to_string(),
+                    value: json!(1)
+                },
+                FieldChange::Changed {
+                    path: "b".to_string(),
+                    old: json!(1),
+                    new: json!(2)
+                },
+                FieldChange::Added {
+                    path: "d".to_string(),
+                    value: json!(1)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_format_value() {
+        let secret = json!("${secrets.DATABASE_URL}");
+        let plain = json!("hunter2");
+
+        assert_eq!(
+            format_value("config.env.DB", &secret, false),
+            "${secrets.DATABASE_URL}"
+        );
+        assert_eq!(format_value("config.env.PASSWORD", &plain, false), MASK);
+        assert_eq!(format_value("config.env.PASSWORD", &plain, true), "hunter2");
+        assert_eq!(format_value("config.image.name", &plain, false), "hunter2");
+        assert_eq!(
+            format_value("target_container_count", &json!(2), false),
+            "2"
+        );
+    }
+}
@@ -0,0 +1,70 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use tokio::fs;
+
+use super::types::EnvFormat;
+use super::utils::serialize_env;
+use crate::state::State;
+
+#[derive(Debug, Parser)]
+#[clap(about = "Export the env variables of a deployment")]
+#[group(skip)]
+pub struct Options {
+    #[clap(help = "Name or ID of the deployment")]
+    pub deployment: Option<String>,
+
+    #[clap(
+        long,
+        help = "Format to export to, `env`, `json` or `yaml`, defaults to `env`"
+    )]
+    pub format: Option<EnvFormat>,
+
+    #[clap(short, long, help = "File to write to, defaults to printing the env")]
+    pub output: Option<PathBuf>,
+
+    #[clap(short, long, help = "Overwrite the file without asking")]
+    pub force: bool,
+}
+
+pub async fn handle(options: Options, state: State) -> Result<()> {
+    let deployment = state
+        .get_deployment_by_opt_name_or_id(options.deployment.as_deref())
+        .await?;
+
+    let content = serialize_env(&deployment.config.env, &options.format.unwrap_or_default())?;
+
+    let Some(output) = options.output else {
+        print!("{content}");
+
+        return Ok(());
+    };
+
+    if output.exists()
+        && !options.force
+        && !dialoguer::Confirm::new()
+            .with_prompt(format!(
+                "{} already exists, overwrite it?",
+                output.display()
+            ))
+            .default(false)
+            .interact_opt()?
+            .unwrap_or(false)
+    {
+        bail!("Aborted by user");
+    }
+
+    fs::write(&output, content)
+        .await
+        .with_context(|| format!("Could not write to {}", output.display()))?;
+
+    log::info!(
+        "Exported {} env variables of `{}` to {}",
+        deployment.config.env.len(),
+        deployment.name,
+        output.display()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use std::path::PathBuf;
+
+use anyhow::{ensure, Result};
+use clap::Parser;
+
+use super::utils::{apply_env, ApplyOptions};
+use crate::commands::ignite::utils::env_file_to_map;
+use crate::state::State;
+
+#[derive(Debug, Parser)]
+#[clap(about = "Import env variables from a .env, json or yaml file")]
+#[group(skip)]
+pub struct Options {
+    #[clap(help = "Name or ID of the deployment")]
+    pub deployment: String,
+
+    #[clap(help = "File to import, json and yaml files are detected by their extension")]
+    pub file: PathBuf,
+
+    #[clap(long, help = "Remove the variables that are not in the file")]
+    pub replace: bool,
+
+    #[clap(flatten)]
+    pub apply: ApplyOptions,
+}
+
+pub async fn handle(options: Options, state: State) -> Result<()> {
+    let deployment = state
+        .get_deployment_by_name_or_id(&options.deployment)
+        .await?;
+
+    let imported = env_file_to_map(options.file.clone()).await?;
+
+    ensure!(
+        !imported.is_empty(),
+        "No env variables found in {}",
+        options.file.display()
+    );
+
+    let env = if options.replace {
+        imported
+    } else {
+        let mut env = deployment.config.env.clone();
+        env.extend(imported);
+        env
+    };
+
+    apply_env(&state, &deployment, env, &options.apply).await
+}
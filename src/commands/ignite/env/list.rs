@@ -0,0 +1,52 @@
+use anyhow::Result;
+use clap::Parser;
+
+use super::utils::format_env;
+use crate::state::State;
+
+#[derive(Debug, Parser)]
+#[clap(about = "List the env variables of a deployment")]
+#[group(skip)]
+pub struct Options {
+    #[clap(help = "Name or ID of the deployment")]
+    pub deployment: Option<String>,
+
+    #[clap(short, long, help = "Only print the keys")]
+    pub quiet: bool,
+
+    #[clap(
+        long,
+        help = "Show the values of env variables instead of masking them"
+    )]
+    pub show_values: bool,
+}
+
+pub async fn handle(options: Options, state: State) -> Result<()> {
+    let deployment = state
+        .get_deployment_by_opt_name_or_id(options.deployment.as_deref())
+        .await?;
+
+    if options.quiet {
+        let mut keys = deployment.config.env.keys().collect::<Vec<_>>();
+        keys.sort();
+
+        for key in keys {
+            println!("{key}");
+        }
+
+        return Ok(());
+    }
+
+    if deployment.config.env.is_empty() {
+        log::info!("`{}` has no env variables", deployment.name);
+
+        return Ok(());
+    }
+
+    println!(
+        "{}",
+        format_env(&deployment.config.env, true, options.show_values).join("\n")
+    );
+
+    Ok(())
+}
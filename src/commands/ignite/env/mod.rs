@@ -0,0 +1,41 @@
+mod export;
+mod import;
+mod list;
+mod set;
+pub mod types;
+mod unset;
+pub mod utils;
+
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+
+use crate::state::State;
+
+#[derive(Debug, Subcommand)]
+pub enum Commands {
+    Set(set::Options),
+    #[clap(alias = "rm")]
+    Unset(unset::Options),
+    #[clap(name = "ls", alias = "list")]
+    List(list::Options),
+    Import(import::Options),
+    Export(export::Options),
+}
+
+#[derive(Debug, Parser)]
+#[clap(about = "Manage the env variables of a deployment")]
+#[group(skip)]
+pub struct Options {
+    #[clap(subcommand)]
+    pub commands: Commands,
+}
+
+pub async fn handle(options: Options, state: State) -> Result<()> {
+    match options.commands {
+        Commands::Set(options) => set::handle(options, state).await,
+        Commands::Unset(options) => unset::handle(options, state).await,
+        Commands::List(options) => list::handle(options, state).await,
+        Commands::Import(options) => import::handle(options, state).await,
+        Commands::Export(options) => export::handle(options, state).await,
+    }
+}
@@ -0,0 +1,34 @@
+use anyhow::Result;
+use clap::Parser;
+
+use super::utils::{apply_env, ApplyOptions};
+use crate::commands::ignite::types::Env;
+use crate::state::State;
+
+#[derive(Debug, Parser)]
+#[clap(about = "Set env variables of a deployment")]
+#[group(skip)]
+pub struct Options {
+    #[clap(help = "Name or ID of the deployment")]
+    pub deployment: String,
+
+    #[clap(
+        required = true,
+        help = "Variables in the form of KEY=VALUE, use KEY=${secrets.NAME} for secrets"
+    )]
+    pub env: Vec<Env>,
+
+    #[clap(flatten)]
+    pub apply: ApplyOptions,
+}
+
+pub async fn handle(options: Options, state: State) -> Result<()> {
+    let deployment = state
+        .get_deployment_by_name_or_id(&options.deployment)
+        .await?;
+
+    let mut env = deployment.config.env.clone();
+    env.extend(options.env.into_iter().map(|Env(key, value)| (key, value)));
+
+    apply_env(&state, &deployment, env, &options.apply).await
+}
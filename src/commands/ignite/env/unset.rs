@@ -0,0 +1,35 @@
+use anyhow::Result;
+use clap::Parser;
+
+use super::utils::{apply_env, ApplyOptions};
+use crate::state::State;
+
+#[derive(Debug, Parser)]
+#[clap(about = "Remove env variables from a deployment")]
+#[group(skip)]
+pub struct Options {
+    #[clap(help = "Name or ID of the deployment")]
+    pub deployment: String,
+
+    #[clap(required = true, help = "Keys of the variables to remove")]
+    pub keys: Vec<String>,
+
+    #[clap(flatten)]
+    pub apply: ApplyOptions,
+}
+
+pub async fn handle(options: Options, state: State) -> Result<()> {
+    let deployment = state
+        .get_deployment_by_name_or_id(&options.deployment)
+        .await?;
+
+    let mut env = deployment.config.env.clone();
+
+    for key in &options.keys {
+        if env.remove(key).is_none() {
+            log::warn!("`{key}` is not set on `{}`", deployment.name);
+        }
+    }
+
+    apply_env(&state, &deployment, env, &options.apply).await
+}
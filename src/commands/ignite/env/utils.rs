@@ -0,0 +1,303 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+
+use anyhow::{bail, Result};
+use clap::Parser;
+use console::style;
+use serde_json::Value;
+use tabwriter::TabWriter;
+
+use super::types::EnvFormat;
+use crate::commands::ignite::diff::types::FieldChange;
+use crate::commands::ignite::diff::utils::{diff_fields, MASK};
+use crate::commands::ignite::history::utils::record_change;
+use crate::commands::ignite::types::{CreateDeployment, Deployment};
+use crate::commands::ignite::utils::{rollout, update_deployment};
+use crate::commands::secrets::utils::get_secret_name;
+use crate::state::State;
+use crate::store::history::Change;
+
+#[derive(Debug, Parser, Clone, Default)]
+#[group(skip)]
+pub struct ApplyOptions {
+    #[clap(long, help = "Roll out new containers with the changed env")]
+    pub rollout: bool,
+
+    #[clap(short, long, help = "Skip the confirmation")]
+    pub yes: bool,
+
+    #[clap(
+        long,
+        help = "Show the values of env variables instead of masking them"
+    )]
+    pub show_values: bool,
+}
+
+/// Shows what changes, then replaces the env of the deployment with `env`
+pub async fn apply_env(
+    state: &State,
+    deployment: &Deployment,
+    env: HashMap<String, String>,
+    options: &ApplyOptions,
+) -> Result<()> {
+    let changes = diff_env(&deployment.config.env, &env);
+
+    if changes.is_empty() {
+        log::info!("The env of `{}` is already up to date", deployment.name);
+
+        return Ok(());
+    }
+
+    for change in &changes {
+        println!("{}", format_env_change(change, options.show_values));
+    }
+
+    if !options.show_values {
+        log::info!("Values are masked, use `--show-values` to reveal them");
+    }
+
+    if !options.yes
+        && !dialoguer::Confirm::new()
+            .with_prompt(format!(
+                "Apply {} changes to the env of `{}`?",
+                changes.len(),
+                deployment.name
+            ))
+            .default(false)
+            .interact_opt()?
+            .unwrap_or(false)
+    {
+        bail!("Aborted by user");
+    }
+
+    let mut update = CreateDeployment::from(deployment.clone());
+    // the name and type can't be changed
+    update.name = None;
+    update.type_ = None;
+    update.env = env;
+
+    let updated = update_deployment(&state.http, &deployment.id, &update).await?;
+
+    record_change(
+        state,
+        &deployment.id,
+        Change::Config {
+            before: Box::new(deployment.config.clone()),
+        },
+    )
+    .await;
+
+    log::info!("Updated the env of `{}`", deployment.name);
+
+    if !updated.can_rollout() {
+        return Ok(());
+    }
+
+    if options.rollout {
+        let rollout = rollout(&state.http, &deployment.id).await?;
+
+        record_change(
+            state,
+            &deployment.id,
+            Change::Rollout {
+                rollout_id: Some(rollout.id),
+                canary: None,
+            },
+        )
+        .await;
+
+        log::info!("Rolling out new containers");
+    } else {
+        log::info!("Running containers keep the old env until the next rollout, use `--rollout` to roll out now");
+    }
+
+    Ok(())
+}
+
+pub fn diff_env(old: &HashMap<String, String>, new: &HashMap<String, String>) -> Vec<FieldChange> {
+    let to_fields = |env: &HashMap<String, String>| {
+        env.iter()
+            .map(|(key, value)| (key.clone(), Value::String(value.clone())))
+            .collect::<BTreeMap<_, _>>()
+    };
+
+    diff_fields(&to_fields(old), &to_fields(new))
+}
+
+/// Secret references are shown by name so they stand out, plain values are masked unless `show_values`
+pub fn format_env_value(value: &str, show_values: bool) -> String {
+    match get_secret_name(value) {
+        Some(name) => style(format!("<secret {name}>")).cyan().to_string(),
+        None if show_values => value.to_string(),
+        None => MASK.to_string(),
+    }
+}
+
+/// Masked values get their length so a change can be told apart without revealing it
+pub fn format_env_change(change: &FieldChange, show_values: bool) -> String {
+    let value = |value: &Value| {
+        let value = value.as_str().unwrap_or_default();
+        let formatted = format_env_value(value, show_values);
+
+        if formatted == MASK {
+            format!(
+                "{MASK} {}",
+                style(format!("({} chars)", value.chars().count())).dim()
+            )
+        } else {
+            formatted
+        }
+    };
+
+    match change {
+        FieldChange::Added { path, value: new } => {
+            format!("{} {path}={}", style("+").green(), value(new))
+        }
+
+        FieldChange::Removed { path, value: old } => {
+            format!("{} {path}={}", style("-").red(), value(old))
+        }
+
+        FieldChange::Changed { path, old, new } => format!(
+            "{} {path}: {} -> {}",
+            style("~").yellow(),
+            value(old),
+            value(new)
+        ),
+    }
+}
+
+pub fn format_env(env: &HashMap<String, String>, title: bool, show_values: bool) -> Vec<String> {
+    let mut tw = TabWriter::new(vec![]);
+
+    if title {
+        writeln!(&mut tw, "KEY\tVALUE").unwrap();
+    }
+
+    for (key, value) in env.iter().collect::<BTreeMap<_, _>>() {
+        writeln!(&mut tw, "{key}\t{}", format_env_value(value, show_values)).unwrap();
+    }
+
+    String::from_utf8(tw.into_inner().unwrap())
+        .unwrap()
+        .lines()
+        .map(std::string::ToString::to_string)
+        .collect()
+}
+
+/// Serializes the env so it can be read back with `hop ignite env import`
+pub fn serialize_env(env: &HashMap<String, String>, format: &EnvFormat) -> Result<String> {
+    let env = env.iter().collect::<BTreeMap<_, _>>();
+
+    Ok(match format {
+        EnvFormat::Env => env
+            .into_iter()
+            .map(|(key, value)| format!("{key}={}\n", dotenv_value(value)))
+            .collect(),
+
+        EnvFormat::Json => format!("{}\n", serde_json::to_string_pretty(&env)?),
+
+        EnvFormat::Yaml => serde_yaml::to_string(&env)?,
+    })
+}
+
+/// Quotes values that would not survive a `.env` file as is
+fn dotenv_value(value: &str) -> String {
+    let plain = value
+        .chars()
+        .all(|c| !c.is_whitespace() && !matches!(c, '#' | '"' | '\'' | '\\' | '`'))
+        // other references would be expanded by the parser when imported
+        && (!value.contains("${") || get_secret_name(value).is_some());
+
+    if plain {
+        value.to_string()
+    } else if !value.contains(['\'', '\n']) {
+        // single quotes keep everything as is
+        format!("'{value}'")
+    } else {
+        let escaped = value
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('$', "\\$")
+            .replace('\n', "\\n");
+
+        format!("\"{escaped}\"")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::dotenv;
+
+    #[test]
+    fn test_dotenv_value() {
+        assert_eq!(dotenv_value("plain"), "plain");
+        assert_eq!(dotenv_value("${secrets.URL}"), "${secrets.URL}");
+        assert_eq!(dotenv_value(""), "");
+        assert_eq!(dotenv_value("with space"), "'with space'");
+        assert_eq!(dotenv_value("a#b"), "'a#b'");
+        assert_eq!(dotenv_value("${HOST}"), "'${HOST}'");
+        assert_eq!(dotenv_value("it's $HOME"), r#""it's \$HOME""#);
+        assert_eq!(dotenv_value("two\nlines"), r#""two\nlines""#);
+    }
+
+    #[test]
+    fn test_serialize_env() {
+        let env = HashMap::from([
+            ("B".to_string(), "2".to_string()),
+            ("A".to_string(), "one two".to_string()),
+        ]);
+
+        assert_eq!(
+            serialize_env(&env, &EnvFormat::Env).unwrap(),
+            "A='one two'\nB=2\n"
+        );
+        assert_eq!(
+            serialize_env(&env, &EnvFormat::Yaml).unwrap(),
+            "A: one two\nB: '2'\n"
+        );
+    }
+
+    #[test]
+    fn test_format_env_value() {
+        assert_eq!(format_env_value("hunter2", false), MASK);
+        assert_eq!(format_env_value("hunter2", true), "hunter2");
+        assert!(format_env_value("${secrets.DB}", false).contains("<secret DB>"));
+    }
+
+    #[test]
+    fn test_format_env_change() {
+        let change = FieldChange::Changed {
+            path: "TOKEN".to_string(),
+            old: Value::String("hunter2".to_string()),
+            new: Value::String("correct horse".to_string()),
+        };
+
+        let masked = format_env_change(&change, false);
+
+        assert!(masked.contains("(7 chars)"));
+        assert!(masked.contains("(13 chars)"));
+        assert!(!masked.contains("hunter2"));
+
+        assert!(format_env_change(&change, true).contains("hunter2"));
+    }
+
+    #[test]
+    fn test_serialize_env_round_trip() {
+        let env = HashMap::from([
+            ("PLAIN".to_string(), "value".to_string()),
+            ("SECRET".to_string(), "${secrets.URL}".to_string()),
+            ("SPACED".to_string(), "a b # c".to_string()),
+            ("QUOTED".to_string(), "it's \"$HOME\"\\".to_string()),
+            ("MULTI".to_string(), "two\nlines".to_string()),
+            ("EMPTY".to_string(), String::new()),
+            ("HOST".to_string(), "x".to_string()),
+            ("URL".to_string(), "${HOST}".to_string()),
+        ]);
+
+        let serialized = serialize_env(&env, &EnvFormat::Env).unwrap();
+
+        assert_eq!(dotenv::parse(&serialized).unwrap(), env);
+    }
+}
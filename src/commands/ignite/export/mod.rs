@@ -0,0 +1,103 @@
+mod types;
+mod utils;
+
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use tokio::fs;
+
+use self::types::ExportFormat;
+use self::utils::{
+    format_k8s_manifests, format_secrets_env_file, get_exported_deployments, to_compose, to_k8s,
+};
+use crate::commands::projects::utils::format_project;
+use crate::state::State;
+
+#[derive(Debug, Parser)]
+#[clap(about = "Export the deployments of a project to other formats")]
+#[group(skip)]
+pub struct Options {
+    #[clap(
+        long,
+        help = "Format to export to, `compose` or `k8s`, defaults to `compose`"
+    )]
+    pub format: Option<ExportFormat>,
+
+    #[clap(
+        short,
+        long,
+        help = "File to write to, defaults to docker-compose.yml for `compose` and k8s.yml for `k8s`"
+    )]
+    pub output: Option<PathBuf>,
+
+    #[clap(short, long, help = "Overwrite existing files without asking")]
+    pub force: bool,
+}
+
+pub async fn handle(options: Options, state: State) -> Result<()> {
+    let project = state.ctx.current_project_error()?;
+
+    log::info!("Exporting project {}", format_project(&project));
+
+    let format = options.format.unwrap_or_default();
+
+    let output = options
+        .output
+        .unwrap_or_else(|| PathBuf::from(format.default_file_name()));
+
+    let parent_dir = output
+        .parent()
+        .with_context(|| format!("Could not get parent directory of {}", output.display()))?
+        .to_path_buf();
+
+    let deployments = get_exported_deployments(&state.http, &project.id).await?;
+
+    log::info!("Found {} deployments", deployments.len());
+
+    let mut files = vec![];
+
+    match format {
+        ExportFormat::Compose => {
+            let (compose, secret_files) = to_compose(&deployments);
+
+            files.push((output, serde_yaml::to_string(&compose)?));
+
+            for secret_file in secret_files {
+                files.push((
+                    parent_dir.join(&secret_file.file_name),
+                    format_secrets_env_file(&secret_file),
+                ));
+            }
+        }
+
+        ExportFormat::K8s => {
+            let manifests = to_k8s(&deployments)?;
+
+            files.push((output, format_k8s_manifests(&manifests)?));
+        }
+    }
+
+    for (path, content) in files {
+        if path.exists()
+            && !options.force
+            && !dialoguer::Confirm::new()
+                .with_prompt(format!("{} already exists, overwrite it?", path.display()))
+                .default(false)
+                .interact_opt()?
+                .unwrap_or(false)
+        {
+            bail!("Aborted by user");
+        }
+
+        fs::write(&path, content)
+            .await
+            .with_context(|| format!("Could not write to {}", path.display()))?;
+
+        log::info!("Wrote {}", path.display());
+    }
+
+    log::info!("Exported project `{}` as {format}", project.namespace);
+
+    Ok(())
+}
@@ -0,0 +1,123 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::commands::gateways::types::Gateway;
+use crate::commands::ignite::health::types::HealthCheck;
+use crate::commands::ignite::types::Deployment;
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Compose,
+    #[serde(alias = "kubernetes")]
+    K8s,
+}
+
+impl ExportFormat {
+    pub const fn default_file_name(&self) -> &'static str {
+        match self {
+            Self::Compose => "docker-compose.yml",
+            Self::K8s => "k8s.yml",
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        serde_json::from_str(&format!("\"{}\"", s.to_lowercase())).map_err(|e| anyhow!(e))
+    }
+}
+
+impl Display for ExportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            serde_json::to_string(self).unwrap().replace('"', "")
+        )
+    }
+}
+
+/// A deployment with everything attached to it that is needed for an export
+#[derive(Debug)]
+pub struct ExportedDeployment {
+    pub deployment: Deployment,
+    pub gateways: Vec<Gateway>,
+    pub health_checks: Vec<HealthCheck>,
+}
+
+/// Subset of the compose spec that `hop ignite from-compose` can read back
+#[derive(Debug, Serialize, Default)]
+pub struct ComposeFile {
+    pub services: BTreeMap<String, ComposeService>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub volumes: BTreeMap<String, ComposeVolume>,
+}
+
+#[derive(Debug, Serialize, Default)]
+pub struct ComposeService {
+    pub image: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub restart: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entrypoint: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub environment: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub env_file: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ports: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub expose: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub volumes: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub healthcheck: Option<ComposeHealthcheck>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ComposeHealthcheck {
+    pub test: Vec<String>,
+    pub interval: String,
+    pub timeout: String,
+    pub retries: u64,
+    pub start_period: String,
+}
+
+#[derive(Debug, Serialize, Default)]
+pub struct ComposeVolume {}
+
+/// Env file with placeholders for the secrets a service uses
+#[derive(Debug)]
+pub struct SecretsEnvFile {
+    pub file_name: String,
+    /// env key and the name of the secret it references
+    pub entries: BTreeMap<String, String>,
+}
+
+/// A single Kubernetes object, the body holds `spec`, `data` etc.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct K8sManifest {
+    pub api_version: String,
+    pub kind: String,
+    pub metadata: K8sMetadata,
+    #[serde(flatten)]
+    pub body: Map<String, Value>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct K8sMetadata {
+    pub name: String,
+    pub labels: BTreeMap<String, String>,
+}
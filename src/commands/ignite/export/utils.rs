@@ -0,0 +1,610 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use serde_json::{json, Value};
+
+use super::types::{
+    ComposeFile, ComposeHealthcheck, ComposeService, ComposeVolume, ExportedDeployment,
+    K8sManifest, K8sMetadata, SecretsEnvFile,
+};
+use crate::commands::containers::types::ContainerType;
+use crate::commands::gateways::types::GatewayType;
+use crate::commands::gateways::util::get_all_gateways;
+use crate::commands::ignite::health::types::HealthCheck;
+use crate::commands::ignite::health::utils::get_all_health_checks;
+use crate::commands::ignite::types::RestartPolicy;
+use crate::commands::ignite::utils::get_all_deployments;
+use crate::commands::secrets::utils::get_secret_name;
+use crate::state::http::HttpClient;
+use crate::utils::size::{parse_size, unit_multiplier};
+
+pub async fn get_exported_deployments(
+    http: &HttpClient,
+    project_id: &str,
+) -> Result<Vec<ExportedDeployment>> {
+    let mut exported = vec![];
+
+    for deployment in get_all_deployments(http, project_id).await? {
+        let (gateways, health_checks) = tokio::join!(
+            get_all_gateways(http, &deployment.id),
+            get_all_health_checks(http, &deployment.id)
+        );
+
+        exported.push(ExportedDeployment {
+            deployment,
+            gateways: gateways?,
+            health_checks: health_checks?,
+        });
+    }
+
+    Ok(exported)
+}
+
+pub fn to_compose(deployments: &[ExportedDeployment]) -> (ComposeFile, Vec<SecretsEnvFile>) {
+    let mut compose = ComposeFile::default();
+    let mut secret_files = vec![];
+
+    for exported in deployments {
+        let deployment = &exported.deployment;
+        let config = &deployment.config;
+
+        let mut service = ComposeService {
+            image: config.image.name.clone(),
+            restart: config.restart_policy.as_ref().map(restart_to_compose),
+            entrypoint: config.entrypoint.clone(),
+            command: config.cmd.clone(),
+            ..Default::default()
+        };
+
+        let mut secrets = BTreeMap::new();
+
+        for (key, value) in &config.env {
+            match get_secret_name(value) {
+                Some(secret) => {
+                    secrets.insert(key.clone(), secret);
+                }
+
+                // compose interpolates `$` when loading the file, `$$` keeps it literal
+                None => {
+                    service
+                        .environment
+                        .insert(key.clone(), value.replace('$', "$$"));
+                }
+            }
+        }
+
+        if !secrets.is_empty() {
+            let file_name = format!("{}.secrets.env", deployment.name);
+
+            service.env_file.push(file_name.clone());
+
+            secret_files.push(SecretsEnvFile {
+                file_name,
+                entries: secrets,
+            });
+        }
+
+        for gateway in &exported.gateways {
+            let Some(port) = gateway.target_port else {
+                continue;
+            };
+
+            match gateway.type_ {
+                GatewayType::External => service.ports.push(format!("{port}:{port}")),
+                GatewayType::Internal => service.expose.push(port.to_string()),
+            }
+        }
+
+        if let Some(volume) = &config.volume {
+            let name = format!("{}-data", deployment.name);
+
+            service
+                .volumes
+                .push(format!("{name}:{}", volume.mount_path));
+
+            compose.volumes.insert(name, ComposeVolume::default());
+        }
+
+        // compose only supports a single health check per service
+        service.healthcheck = exported.health_checks.first().map(health_check_to_compose);
+
+        compose.services.insert(deployment.name.clone(), service);
+    }
+
+    (compose, secret_files)
+}
+
+fn restart_to_compose(policy: &RestartPolicy) -> String {
+    match policy {
+        RestartPolicy::Never => "no",
+        RestartPolicy::Always => "always",
+        RestartPolicy::OnFailure => "on-failure",
+    }
+    .to_string()
+}
+
+fn health_check_to_compose(check: &HealthCheck) -> ComposeHealthcheck {
+    let test = if check.protocol.eq_ignore_ascii_case("tcp") {
+        vec![
+            "CMD".to_string(),
+            "nc".to_string(),
+            "-z".to_string(),
+            "localhost".to_string(),
+            check.port.to_string(),
+        ]
+    } else {
+        vec![
+            "CMD".to_string(),
+            "curl".to_string(),
+            "-f".to_string(),
+            format!("http://localhost:{}{}", check.port, check.path),
+        ]
+    };
+
+    ComposeHealthcheck {
+        test,
+        interval: format!("{}s", check.interval),
+        timeout: format!("{}s", check.timeout),
+        retries: check.max_retries,
+        start_period: format!("{}s", check.initial_delay),
+    }
+}
+
+pub fn format_secrets_env_file(file: &SecretsEnvFile) -> String {
+    let mut buff =
+        vec!["# Placeholders for Hop secrets, fill in the values before running".to_string()];
+
+    for (key, secret) in &file.entries {
+        buff.push(format!("# secret: {secret}"));
+        buff.push(format!("{key}="));
+    }
+
+    buff.join("\n") + "\n"
+}
+
+pub fn to_k8s(deployments: &[ExportedDeployment]) -> Result<Vec<K8sManifest>> {
+    let mut manifests = vec![];
+
+    for exported in deployments {
+        let deployment = &exported.deployment;
+        let config = &deployment.config;
+        let name = &deployment.name;
+        let secrets_name = format!("{name}-secrets");
+
+        let mut env = vec![];
+        let mut secrets = BTreeMap::new();
+
+        let mut env_keys = config.env.keys().collect::<Vec<_>>();
+        env_keys.sort();
+
+        for key in env_keys {
+            let value = &config.env[key];
+
+            match get_secret_name(value) {
+                Some(secret) => {
+                    env.push(json!({
+                        "name": key,
+                        "valueFrom": {
+                            "secretKeyRef": { "name": secrets_name, "key": secret }
+                        }
+                    }));
+
+                    secrets.insert(secret, String::new());
+                }
+
+                None => env.push(json!({ "name": key, "value": value })),
+            }
+        }
+
+        let ports = exported
+            .gateways
+            .iter()
+            .filter_map(|gateway| gateway.target_port)
+            .collect::<Vec<_>>();
+
+        let mut container = json!({
+            "name": name,
+            "image": config.image.name,
+            "env": env,
+            "ports": ports
+                .iter()
+                .map(|port| json!({ "containerPort": port }))
+                .collect::<Vec<_>>(),
+            "resources": {
+                "limits": {
+                    "cpu": config.resources.vcpu.to_string(),
+                    "memory": to_k8s_quantity(&config.resources.ram)?,
+                }
+            },
+        });
+
+        // docker entrypoint is the k8s command and docker cmd are the k8s args
+        if let Some(entrypoint) = &config.entrypoint {
+            container["command"] = json!(entrypoint);
+        }
+
+        if let Some(cmd) = &config.cmd {
+            container["args"] = json!(cmd);
+        }
+
+        // k8s supports a single liveness probe per container
+        if let Some(check) = exported.health_checks.first() {
+            let mut probe = json!({
+                "initialDelaySeconds": check.initial_delay,
+                "periodSeconds": check.interval,
+                "timeoutSeconds": check.timeout,
+                "failureThreshold": check.max_retries,
+                "successThreshold": check.success_threshold,
+            });
+
+            if check.protocol.eq_ignore_ascii_case("tcp") {
+                probe["tcpSocket"] = json!({ "port": check.port });
+            } else {
+                probe["httpGet"] = json!({ "path": check.path, "port": check.port });
+            }
+
+            container["livenessProbe"] = probe;
+        }
+
+        let mut pod_spec = json!({ "containers": [container] });
+
+        if let Some(volume) = &config.volume {
+            let claim = format!("{name}-data");
+
+            manifests.push(k8s_manifest(
+                "v1",
+                "PersistentVolumeClaim",
+                &claim,
+                json!({
+                    "spec": {
+                        "accessModes": ["ReadWriteOnce"],
+                        "resources": {
+                            "requests": { "storage": to_k8s_quantity(&volume.size)? }
+                        }
+                    }
+                }),
+            ));
+
+            pod_spec["volumes"] = json!([{
+                "name": claim,
+                "persistentVolumeClaim": { "claimName": claim }
+            }]);
+
+            pod_spec["containers"][0]["volumeMounts"] = json!([{
+                "name": claim,
+                "mountPath": volume.mount_path
+            }]);
+        }
+
+        if !secrets.is_empty() {
+            log::warn!(
+                "Secrets of `{name}` are exported as empty stubs, fill them in before applying"
+            );
+
+            manifests.push(k8s_manifest(
+                "v1",
+                "Secret",
+                &secrets_name,
+                json!({ "type": "Opaque", "stringData": secrets }),
+            ));
+        }
+
+        let mut spec = json!({
+            "selector": { "matchLabels": { "app": name } },
+            "template": {
+                "metadata": { "labels": { "app": name } },
+                "spec": pod_spec,
+            },
+        });
+
+        let kind = if config.type_ == ContainerType::Stateful {
+            spec["replicas"] = json!(1);
+            spec["serviceName"] = json!(name);
+
+            "StatefulSet"
+        } else {
+            spec["replicas"] = json!(deployment.target_container_count);
+
+            "Deployment"
+        };
+
+        manifests.push(k8s_manifest("apps/v1", kind, name, json!({ "spec": spec })));
+
+        if !ports.is_empty() {
+            manifests.push(k8s_manifest(
+                "v1",
+                "Service",
+                name,
+                json!({
+                    "spec": {
+                        "selector": { "app": name },
+                        "ports": ports
+                            .iter()
+                            .map(|port| json!({
+                                "name": format!("port-{port}"),
+                                "port": port,
+                                "targetPort": port,
+                            }))
+                            .collect::<Vec<_>>(),
+                    }
+                }),
+            ));
+        }
+
+        let rules = exported
+            .gateways
+            .iter()
+            .filter(|gateway| gateway.type_ == GatewayType::External)
+            .flat_map(|gateway| {
+                let port = gateway.target_port;
+
+                gateway
+                    .domains
+                    .iter()
+                    .map(|domain| domain.domain.clone())
+                    .chain(gateway.hopsh_domain.clone())
+                    .map(move |host| {
+                        json!({
+                            "host": host,
+                            "http": {
+                                "paths": [{
+                                    "path": "/",
+                                    "pathType": "Prefix",
+                                    "backend": {
+                                        "service": { "name": name, "port": { "number": port } }
+                                    }
+                                }]
+                            }
+                        })
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        if !rules.is_empty() {
+            manifests.push(k8s_manifest(
+                "networking.k8s.io/v1",
+                "Ingress",
+                name,
+                json!({ "spec": { "rules": rules } }),
+            ));
+        }
+    }
+
+    Ok(manifests)
+}
+
+fn k8s_manifest(api_version: &str, kind: &str, name: &str, body: Value) -> K8sManifest {
+    K8sManifest {
+        api_version: api_version.to_string(),
+        kind: kind.to_string(),
+        metadata: K8sMetadata {
+            name: name.to_string(),
+            labels: BTreeMap::from([("app".to_string(), name.to_string())]),
+        },
+        body: body.as_object().cloned().unwrap_or_default(),
+    }
+}
+
+/// Converts sizes like `512M` to a Kubernetes quantity like `512Mi`
+fn to_k8s_quantity(size: &str) -> Result<String> {
+    Ok(format!("{}Mi", parse_size(size)? / unit_multiplier::MB))
+}
+
+pub fn format_k8s_manifests(manifests: &[K8sManifest]) -> Result<String> {
+    let documents = manifests
+        .iter()
+        .map(serde_yaml::to_string)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(documents.join("---\n"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::commands::gateways::types::Gateway;
+    use crate::commands::ignite::from_compose::types::DockerCompose;
+    use crate::commands::ignite::types::{Config, Deployment, Image, Volume};
+
+    #[test]
+    fn test_compose_export_can_be_imported() {
+        let deployment = Deployment {
+            name: "api".to_string(),
+            config: Config {
+                image: Image {
+                    name: "registry.hop.io/ns/api".to_string(),
+                },
+                env: [
+                    ("PORT".to_string(), "8080".to_string()),
+                    ("TOKEN".to_string(), "${secrets.API_TOKEN}".to_string()),
+                ]
+                .into(),
+                restart_policy: Some(RestartPolicy::Always),
+                volume: Some(Volume::default()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let gateway = Gateway {
+            target_port: Some(8080),
+            type_: GatewayType::External,
+            ..Default::default()
+        };
+
+        let health_check = serde_json::from_value::<HealthCheck>(serde_json::json!({
+            "id": "health_check_1",
+            "deployment_id": "deployment_1",
+            "initial_delay": 5,
+            "interval": 60,
+            "max_retries": 3,
+            "path": "/health",
+            "protocol": "HTTP",
+            "port": 8080,
+            "timeout": 50,
+            "success_threshold": 1,
+            "created_at": "",
+            "type": "liveness",
+        }))
+        .unwrap();
+
+        let (compose, secret_files) = to_compose(&[ExportedDeployment {
+            deployment,
+            gateways: vec![gateway],
+            health_checks: vec![health_check],
+        }]);
+
+        assert_eq!(secret_files.len(), 1);
+        assert_eq!(
+            secret_files[0].entries.get("TOKEN"),
+            Some(&"API_TOKEN".to_string())
+        );
+
+        let yaml = serde_yaml::to_string(&compose).unwrap();
+        let parsed = serde_yaml::from_str::<DockerCompose>(&yaml).unwrap();
+
+        let service = parsed.services.unwrap().remove("api").unwrap();
+
+        assert_eq!(service.image, Some("registry.hop.io/ns/api".to_string()));
+        assert_eq!(service.ports.unwrap()[0].0, 8080);
+        assert!(service.healthcheck.is_some());
+        assert_eq!(service.volumes.unwrap().1, "/data");
+    }
+
+    #[test]
+    fn test_tcp_health_check_to_compose() {
+        let check = serde_json::from_value::<HealthCheck>(serde_json::json!({
+            "id": "health_check_1",
+            "deployment_id": "deployment_1",
+            "initial_delay": 5,
+            "interval": 60,
+            "max_retries": 3,
+            "path": "/",
+            "protocol": "TCP",
+            "port": 5432,
+            "timeout": 50,
+            "success_threshold": 1,
+            "created_at": "",
+            "type": "liveness",
+        }))
+        .unwrap();
+
+        assert_eq!(
+            health_check_to_compose(&check).test,
+            ["CMD", "nc", "-z", "localhost", "5432"]
+        );
+    }
+
+    #[test]
+    fn test_compose_escapes_interpolation() {
+        let deployment = Deployment {
+            name: "api".to_string(),
+            config: Config {
+                env: [("PRICE".to_string(), "$5 or ${HOME}".to_string())].into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let (compose, _) = to_compose(&[ExportedDeployment {
+            deployment,
+            gateways: vec![],
+            health_checks: vec![],
+        }]);
+
+        assert_eq!(
+            compose.services["api"].environment["PRICE"],
+            "$$5 or $${HOME}"
+        );
+    }
+
+    #[test]
+    fn test_k8s_tcp_liveness_probe() {
+        let deployment = Deployment {
+            name: "db".to_string(),
+            ..Default::default()
+        };
+
+        let check = serde_json::from_value::<HealthCheck>(serde_json::json!({
+            "id": "health_check_1",
+            "deployment_id": "deployment_1",
+            "initial_delay": 5,
+            "interval": 60,
+            "max_retries": 3,
+            "path": "/",
+            "protocol": "TCP",
+            "port": 5432,
+            "timeout": 50,
+            "success_threshold": 1,
+            "created_at": "",
+            "type": "liveness",
+        }))
+        .unwrap();
+
+        let manifests = to_k8s(&[ExportedDeployment {
+            deployment,
+            gateways: vec![],
+            health_checks: vec![check],
+        }])
+        .unwrap();
+
+        let workload = manifests
+            .iter()
+            .find(|manifest| manifest.kind == "Deployment")
+            .unwrap();
+        let probe = &workload.body["spec"]["template"]["spec"]["containers"][0]["livenessProbe"];
+
+        assert_eq!(probe["tcpSocket"]["port"], 5432);
+        assert!(probe.get("httpGet").is_none());
+    }
+
+    #[test]
+    fn test_k8s_export() {
+        let deployment = Deployment {
+            name: "db".to_string(),
+            target_container_count: 1,
+            config: Config {
+                type_: ContainerType::Stateful,
+                image: Image {
+                    name: "postgres".to_string(),
+                },
+                env: [(
+                    "POSTGRES_PASSWORD".to_string(),
+                    "${secrets.DB_PASSWORD}".to_string(),
+                )]
+                .into(),
+                volume: Some(Volume::default()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let gateway = Gateway {
+            target_port: Some(5432),
+            type_: GatewayType::Internal,
+            ..Default::default()
+        };
+
+        let manifests = to_k8s(&[ExportedDeployment {
+            deployment,
+            gateways: vec![gateway],
+            health_checks: vec![],
+        }])
+        .unwrap();
+
+        let kinds = manifests
+            .iter()
+            .map(|manifest| manifest.kind.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            kinds,
+            ["PersistentVolumeClaim", "Secret", "StatefulSet", "Service"]
+        );
+
+        assert_eq!(
+            manifests[0].body["spec"]["resources"]["requests"]["storage"],
+            "3072Mi"
+        );
+    }
+}
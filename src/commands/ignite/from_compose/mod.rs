@@ -1,7 +1,6 @@
-mod types;
+pub mod types;
 pub mod utils;
 
-use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 
 use anyhow::{bail, Context, Result};
@@ -10,31 +9,60 @@ use leap_client_rs::{LeapEdge, LeapOptions};
 use regex::bytes::Regex;
 use tokio::fs;
 
-use self::types::DockerCompose;
-use self::utils::order_by_dependencies;
+use self::types::{DockerCompose, Port, Service, ServicePlan};
+use self::utils::{
+    apply_service_to_deployment, format_deployment_diff, gateway_template, health_check_matches,
+    order_by_dependencies, service_ports, service_to_deployment_name,
+};
 use crate::commands::auth::docker::HOP_REGISTRY_URL;
+use crate::commands::containers::types::ContainerOptions;
 use crate::commands::deploy::{builder, local};
-use crate::commands::gateways::types::GatewayConfig;
-use crate::commands::gateways::util::{create_gateway, update_gateway_config};
+use crate::commands::gateways::types::{Gateway, GatewayConfig};
+use crate::commands::gateways::util::{create_gateway, get_all_gateways, update_gateway_config};
 use crate::commands::ignite::create::Options as CreateOptions;
 use crate::commands::ignite::from_compose::types::ServiceBuildUnion;
 use crate::commands::ignite::health::types::CreateHealthCheck;
-use crate::commands::ignite::health::utils::create_health_check;
-use crate::commands::ignite::types::{Deployment, Image};
+use crate::commands::ignite::health::utils::{
+    create_health_check, delete_health_check, get_all_health_checks,
+};
+use crate::commands::ignite::history::utils::record_change;
+use crate::commands::ignite::types::{CreateDeployment, Deployment, Image};
 use crate::commands::ignite::utils::{
-    create_deployment, scale, update_deployment_config, WEB_IGNITE_URL,
+    create_deployment, get_all_deployments, rollout, scale, update_deployment,
+    update_deployment_config, WEB_IGNITE_URL,
 };
+use crate::commands::projects::types::Project;
 use crate::config::LEAP_PROJECT;
 use crate::state::State;
+use crate::store::history::Change;
 use crate::store::hopfile::HopFile;
 use crate::utils::urlify;
 
 #[derive(Debug, Parser)]
-#[clap(about = "Creates new Ignite deployments from a Docker compose file")]
+#[clap(about = "Creates or updates Ignite deployments from a Docker compose file")]
 #[group(skip)]
 pub struct Options {
     #[clap(help = "The file to read from. Defaults to docker-compose.yml")]
     pub file: Option<PathBuf>,
+
+    #[clap(
+        long,
+        help = "Roll out existing deployments that were updated or rebuilt"
+    )]
+    pub rollout: bool,
+
+    #[clap(
+        short,
+        long,
+        help = "Apply changes to existing deployments without asking"
+    )]
+    pub yes: bool,
+
+    #[clap(
+        long,
+        help = "Upload files that look like secrets, like .env or private keys, without asking"
+    )]
+    pub allow_secrets: bool,
 }
 
 pub async fn handle(options: Options, state: State) -> Result<()> {
@@ -100,118 +128,40 @@ pub async fn handle(options: Options, state: State) -> Result<()> {
 
     let project = state.ctx.current_project_error()?;
 
-    // let deployments = get_all_deployments(&state.http, &project.id).await?;
+    let existing_deployments = get_all_deployments(&state.http, &project.id).await?;
 
     let services = compose.services.unwrap_or_default();
     // let volumes = compose.volumes.unwrap_or_default();
 
     let mut services = services.iter().collect::<Vec<_>>();
 
-    order_by_dependencies(&mut services);
+    order_by_dependencies(&mut services)?;
 
     log::info!("Creating deployments from {}", file.display());
     log::info!("Found {} services", services.len());
 
     log::info!("Using project `{}` ({})", project.name, project.namespace);
 
-    let mut deployments_with_extras = vec![];
+    let mut plans = vec![];
 
     for (name, service) in services {
-        log::info!("Creating deployment for {name}");
-
-        let deployment: Deployment = service.clone().into();
-
-        let mut deployment_config = update_deployment_config(
-            &state.http,
-            CreateOptions {
-                config: Default::default(),
-                // temporary value that gets replaced after we get the name
-                image: if service.build.is_some() {
-                    Some("".to_string())
-                } else {
-                    service.image.clone()
-                },
-            },
-            true,
-            &deployment,
-            &Some(name.clone()),
-            false,
-            &project,
-        )
-        .await?;
-
-        let dep_name = deployment_config
-            .0
-            .name
-            .clone()
-            .unwrap_or_else(|| name.clone());
-
-        // looks so bad but basically it joins both `ports` and `expose` into a single
-        // list then parses the port if its port:port or port format
-        let gateways = {
-            let ports = HashSet::<_>::from_iter(
-                service
-                    .expose
-                    .clone()
-                    .unwrap_or_default()
-                    .into_iter()
-                    .chain(service.ports.clone().unwrap_or_default().into_iter()),
-            );
-
-            log::debug!("Found ports: {:?}", ports);
-
-            let mut gateways = vec![];
-
-            for port in ports {
-                println!();
-
-                log::info!("Found port `{port}` in the compose file for `{name}`");
-
-                let config = GatewayConfig {
-                    target_port: Some(port.0),
-                    internal_domain: Some(format!("{dep_name}.hop")),
-                    ..Default::default()
-                };
-
-                let gateway_config =
-                    update_gateway_config(&Default::default(), false, false, &config)?;
-
-                gateways.push(gateway_config);
-            }
-
-            gateways
+        let existing = existing_deployments
+            .iter()
+            .find(|d| d.name == service_to_deployment_name(name))
+            .cloned();
+
+        let plan = match existing {
+            Some(existing) => plan_existing(&state, name, service, existing, options.yes).await?,
+            None => plan_new(&state, name, service, &project).await?,
         };
 
-        if deployment_config
-            .0
-            .image
-            .clone()
-            .unwrap_or_default()
-            .name
-            .is_empty()
-        {
-            log::info!("The image for `{name}` will be built by the Hop CLI and pushed to the Hop registry");
-
-            deployment_config.0.image = Some(Image {
-                name: format!("{}/{}/{}", HOP_REGISTRY_URL, project.namespace, dep_name),
-            });
-        }
-
-        deployments_with_extras.push((
-            deployment_config.0,
-            deployment_config.1,
-            service.build.clone(),
-            gateways,
-            service.healthcheck.clone().map(CreateHealthCheck::from),
-        ));
+        plans.push(plan);
 
         // add a new line
         println!();
     }
 
-    let has_unbuilt = deployments_with_extras
-        .iter()
-        .any(|(_, _, build, _, _)| build.is_some());
+    let has_unbuilt = plans.iter().any(|plan| plan.build.is_some());
 
     let build_localy = if has_unbuilt {
         log::info!("Some of the services in the compose file require building. They can be built locally or on our build servers");
@@ -240,11 +190,53 @@ pub async fn handle(options: Options, state: State) -> Result<()> {
     // all projects should already be subscribed but this is a precaution
     leap.channel_subscribe(&project.id).await?;
 
-    for (deployment, containers, builder, gateways, health_checks) in deployments_with_extras {
-        let dep = create_deployment(&state.http, &project.id, &deployment).await?;
-        log::info!("Created deployment `{}`", dep.name);
+    for plan in plans {
+        let is_existing = plan.existing.is_some();
+        let changed = plan.update_config || plan.build.is_some();
+
+        let dep = match plan.existing {
+            Some(existing) if plan.update_config => {
+                // mirror the update flow, the name and type can't be changed
+                let config = CreateDeployment {
+                    name: None,
+                    type_: None,
+                    ..plan.config
+                };
+
+                let dep = update_deployment(&state.http, &existing.id, &config).await?;
+                log::info!("Updated deployment `{}`", dep.name);
+
+                record_change(
+                    &state,
+                    &dep.id,
+                    Change::Config {
+                        before: Box::new(existing.config),
+                    },
+                )
+                .await;
+
+                dep
+            }
+
+            Some(existing) => existing,
+
+            None => {
+                let dep = create_deployment(&state.http, &project.id, &plan.config).await?;
+                log::info!("Created deployment `{}`", dep.name);
+
+                if let Some(count) = plan.containers.containers {
+                    if dep.can_scale() && count > 0 {
+                        scale(&state.http, &dep.id, count).await?;
+
+                        log::info!("Created {count} containers");
+                    }
+                }
+
+                dep
+            }
+        };
 
-        if let Some(build) = builder {
+        if let Some(build) = plan.build {
             let path = match build {
                 ServiceBuildUnion::Map { context, .. } => context,
                 ServiceBuildUnion::String(context) => context,
@@ -266,34 +258,41 @@ pub async fn handle(options: Options, state: State) -> Result<()> {
             log::info!("Created hop.yml for `{}`", dep.name);
 
             if build_localy {
-                local::build(&state, &dep.config.image.name, path, &dep.config.env).await?;
+                local::build(&state, &dep.config.image.name, path, &dep.config.env, &[]).await?;
             } else {
-                builder::build(&state, &project.id, &dep.id, path, &mut leap).await?;
+                builder::check_secrets(&path, options.allow_secrets).await?;
+                builder::build(&state, &project.id, &dep.id, path, None, &mut leap).await?;
             }
         }
 
-        if let Some(count) = containers.containers {
-            if dep.can_scale() && count > 0 {
-                scale(&state.http, &dep.id, count).await?;
-
-                log::info!("Created {count} containers");
-            }
-        }
-
-        for gateway in gateways {
+        for gateway in plan.gateways {
             create_gateway(&state.http, &dep.id, &gateway).await?;
             log::info!("Created gateway for `{}`", dep.name);
         }
 
-        if let Some(health_check) = health_checks {
-            if !dep.is_ephemeral() {
-                create_health_check(&state.http, &dep.id, health_check).await?;
-                log::info!("Created health check for `{}`", dep.name);
-            } else {
+        if let Some(health_check) = plan.health_check {
+            if dep.is_ephemeral() {
                 log::warn!("Health checks are not supported for ephemeral deployments, skipping");
+            } else {
+                sync_health_check(&state, &dep, health_check, is_existing).await?;
             }
         }
 
+        if options.rollout && is_existing && changed && dep.can_rollout() {
+            let rollout = rollout(&state.http, &dep.id).await?;
+            log::info!("Rolling out new containers for `{}`", dep.name);
+
+            record_change(
+                &state,
+                &dep.id,
+                Change::Rollout {
+                    rollout_id: Some(rollout.id),
+                    canary: None,
+                },
+            )
+            .await;
+        }
+
         println!();
     }
 
@@ -306,3 +305,192 @@ pub async fn handle(options: Options, state: State) -> Result<()> {
 
     Ok(())
 }
+
+async fn plan_new(
+    state: &State,
+    name: &String,
+    service: &Service,
+    project: &Project,
+) -> Result<ServicePlan> {
+    log::info!("Creating deployment for {name}");
+
+    let deployment: Deployment = service.clone().into();
+
+    let (mut config, containers) = update_deployment_config(
+        &state.http,
+        CreateOptions {
+            config: Default::default(),
+            // temporary value that gets replaced after we get the name
+            image: if service.build.is_some() {
+                Some("".to_string())
+            } else {
+                service.image.clone()
+            },
+        },
+        true,
+        &deployment,
+        &Some(name.clone()),
+        false,
+        project,
+    )
+    .await?;
+
+    let dep_name = config.name.clone().unwrap_or_else(|| name.clone());
+
+    let gateways = create_gateway_configs(name, &dep_name, service, &[])?;
+
+    if config.image.clone().unwrap_or_default().name.is_empty() {
+        log::info!(
+            "The image for `{name}` will be built by the Hop CLI and pushed to the Hop registry"
+        );
+
+        config.image = Some(Image {
+            name: format!("{}/{}/{}", HOP_REGISTRY_URL, project.namespace, dep_name),
+        });
+    }
+
+    Ok(ServicePlan {
+        config,
+        containers,
+        build: service.build.clone(),
+        gateways,
+        health_check: service.healthcheck.clone().map(CreateHealthCheck::from),
+        existing: None,
+        update_config: false,
+    })
+}
+
+async fn plan_existing(
+    state: &State,
+    name: &String,
+    service: &Service,
+    existing: Deployment,
+    yes: bool,
+) -> Result<ServicePlan> {
+    log::info!(
+        "Found existing deployment `{}` ({}) for {name}",
+        existing.name,
+        existing.id
+    );
+
+    let config = apply_service_to_deployment(&existing, service);
+    let diff = format_deployment_diff(&CreateDeployment::from(existing.clone()), &config);
+
+    let update_config = if diff.is_empty() {
+        log::info!("Deployment `{}` is up to date", existing.name);
+
+        false
+    } else {
+        log::info!("Changes for `{}`:", existing.name);
+
+        for line in &diff {
+            println!("  {line}");
+        }
+
+        yes || dialoguer::Confirm::new()
+            .with_prompt("Do you want to apply these changes?")
+            .default(true)
+            .interact()?
+    };
+
+    let existing_gateways = get_all_gateways(&state.http, &existing.id).await?;
+
+    let gateways = create_gateway_configs(name, &existing.name, service, &existing_gateways)?;
+
+    Ok(ServicePlan {
+        config,
+        containers: ContainerOptions::from_deployment(&existing),
+        build: service.build.clone(),
+        gateways,
+        health_check: service.healthcheck.clone().map(CreateHealthCheck::from),
+        existing: Some(existing),
+        update_config,
+    })
+}
+
+// ports that already have a gateway are skipped, the others go through the
+// guided gateway creation
+fn create_gateway_configs(
+    name: &str,
+    dep_name: &str,
+    service: &Service,
+    existing_gateways: &[Gateway],
+) -> Result<Vec<GatewayConfig>> {
+    let ports = service_ports(service);
+
+    log::debug!("Found ports: {:?}", ports);
+
+    let mut gateways = vec![];
+
+    for port in &ports {
+        if existing_gateways
+            .iter()
+            .any(|gateway| gateway.target_port == Some(port.0))
+        {
+            log::info!("Port `{port}` of `{name}` already has a gateway, skipping");
+
+            continue;
+        }
+
+        println!();
+
+        log::info!("Found port `{port}` in the compose file for `{name}`");
+
+        let gateway_config = update_gateway_config(
+            &Default::default(),
+            false,
+            false,
+            &gateway_template(dep_name, port),
+        )?;
+
+        gateways.push(gateway_config);
+    }
+
+    for gateway in existing_gateways {
+        if !gateway
+            .target_port
+            .map(|port| ports.contains(&Port(port)))
+            .unwrap_or_default()
+        {
+            log::warn!(
+                "Gateway `{}` of `{dep_name}` is not in the compose file, leaving it untouched",
+                gateway.id
+            );
+        }
+    }
+
+    Ok(gateways)
+}
+
+// health checks can't be updated, so outdated ones get replaced
+async fn sync_health_check(
+    state: &State,
+    dep: &Deployment,
+    health_check: CreateHealthCheck,
+    is_existing: bool,
+) -> Result<()> {
+    let existing_checks = if is_existing {
+        get_all_health_checks(&state.http, &dep.id).await?
+    } else {
+        vec![]
+    };
+
+    if existing_checks
+        .iter()
+        .any(|check| health_check_matches(check, &health_check))
+    {
+        log::info!("Health check for `{}` is up to date", dep.name);
+
+        return Ok(());
+    }
+
+    for check in existing_checks {
+        delete_health_check(&state.http, &check.id).await?;
+        log::info!("Deleted outdated health check for `{}`", dep.name);
+    }
+
+    create_health_check(&state.http, &dep.id, health_check).await?;
+    log::info!("Created health check for `{}`", dep.name);
+
+    Ok(())
+}
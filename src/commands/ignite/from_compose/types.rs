@@ -8,9 +8,12 @@ use serde::Deserialize;
 use serde_yaml::Value;
 
 use super::utils::get_seconds_from_docker_duration;
-use crate::commands::containers::types::ContainerType;
+use crate::commands::containers::types::{ContainerOptions, ContainerType};
+use crate::commands::gateways::types::GatewayConfig;
 use crate::commands::ignite::health::types::CreateHealthCheck;
-use crate::commands::ignite::types::{Config, Deployment, Image, RestartPolicy, Volume};
+use crate::commands::ignite::types::{
+    Config, CreateDeployment, Deployment, Image, RestartPolicy, Volume,
+};
 use crate::commands::ignite::utils::{env_file_to_map, get_shell_array};
 use crate::utils::parse_key_val;
 
@@ -96,6 +99,20 @@ impl DockerCompose {
     }
 }
 
+/// Everything that has to be created or updated for a single compose service
+#[derive(Debug)]
+pub struct ServicePlan {
+    pub config: CreateDeployment,
+    pub containers: ContainerOptions,
+    pub build: Option<ServiceBuildUnion>,
+    pub gateways: Vec<GatewayConfig>,
+    pub health_check: Option<CreateHealthCheck>,
+    /// the deployment with the same name in the project, if there is one
+    pub existing: Option<Deployment>,
+    /// false if the existing deployment config is already up to date
+    pub update_config: bool,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
 #[serde(untagged, deny_unknown_fields)]
 pub enum ConfigExternalUnion {
@@ -1,40 +1,83 @@
+use std::collections::{BTreeSet, VecDeque};
+
 use anyhow::{bail, Result};
+use console::style;
 use regex::Regex;
 
-use super::types::Service;
+use super::types::{Port, Service};
+use crate::commands::gateways::types::GatewayConfig;
+use crate::commands::ignite::health::types::{CreateHealthCheck, HealthCheck};
+use crate::commands::ignite::types::{CreateDeployment, Deployment};
 
-// order services by their dependencies
-// unsure of the accuracy of this algorithm but its fine for now
-pub fn order_by_dependencies(services: &mut [(&String, &Service)]) {
-    services.sort_by(|(a_name, a_service), (b_name, b_service)| {
-        let a_depends_on = a_service.depends_on.clone();
-        let b_depends_on = b_service.depends_on.clone();
+/// Anything that can depend on other named services
+pub trait DependsOn {
+    fn depends_on(&self) -> Option<Vec<String>>;
+}
 
-        if a_depends_on.is_none() && b_depends_on.is_none() {
-            return std::cmp::Ordering::Equal;
-        }
+impl DependsOn for Service {
+    fn depends_on(&self) -> Option<Vec<String>> {
+        self.depends_on.clone()
+    }
+}
 
-        if a_depends_on.is_none() && b_depends_on.is_some() {
-            return std::cmp::Ordering::Less;
-        }
+/// Orders services so every service comes after the services it depends on,
+/// services that don't depend on each other keep their order
+pub fn order_by_dependencies<T: DependsOn>(services: &mut [(&String, &T)]) -> Result<()> {
+    // how many dependencies of each service are not ordered yet
+    let mut pending = vec![0; services.len()];
+    let mut dependents = vec![vec![]; services.len()];
 
-        if b_depends_on.is_none() {
-            return std::cmp::Ordering::Greater;
+    for (idx, (name, service)) in services.iter().enumerate() {
+        for dependency in service.depends_on().unwrap_or_default() {
+            let Some(dependency_idx) = services.iter().position(|(name, _)| **name == dependency)
+            else {
+                bail!("Service `{name}` depends on `{dependency}`, which does not exist");
+            };
+
+            pending[idx] += 1;
+            dependents[dependency_idx].push(idx);
         }
+    }
 
-        let a_depends_on = a_depends_on.unwrap();
-        let b_depends_on = b_depends_on.unwrap();
+    let mut ready = (0..services.len())
+        .filter(|idx| pending[*idx] == 0)
+        .collect::<VecDeque<_>>();
+    let mut order = vec![];
 
-        if a_depends_on.contains(b_name) {
-            return std::cmp::Ordering::Less;
-        }
+    while let Some(idx) = ready.pop_front() {
+        order.push(idx);
+
+        for dependent in &dependents[idx] {
+            pending[*dependent] -= 1;
 
-        if b_depends_on.contains(a_name) {
-            return std::cmp::Ordering::Greater;
+            if pending[*dependent] == 0 {
+                ready.push_back(*dependent);
+            }
         }
+    }
+
+    // whatever is left depends on itself through the others
+    if order.len() != services.len() {
+        let cycle = services
+            .iter()
+            .zip(&pending)
+            .filter(|(_, pending)| **pending > 0)
+            .map(|((name, _), _)| format!("`{name}`"))
+            .collect::<Vec<_>>();
+
+        bail!(
+            "Services {} depend on each other in a cycle",
+            cycle.join(", ")
+        );
+    }
+
+    let ordered = order
+        .into_iter()
+        .map(|idx| services[idx])
+        .collect::<Vec<_>>();
+    services.copy_from_slice(&ordered);
 
-        std::cmp::Ordering::Equal
-    });
+    Ok(())
 }
 
 const DURATION_UNITS: [&str; 5] = ["us", "ms", "s", "m", "h"];
@@ -70,3 +113,357 @@ pub fn get_seconds_from_docker_duration(duration: &str) -> Result<u64> {
 
     Ok(out / 1000 / 1000)
 }
+
+/// Normalizes a service name the same way `update_deployment_config` does
+/// with its fallback name, so existing deployments can be matched by name
+pub fn service_to_deployment_name(name: &str) -> String {
+    name.replace(['_', ' ', '.'], "-").to_lowercase()
+}
+
+/// Joins the `expose` and `ports` of the service, without duplicates
+pub fn service_ports(service: &Service) -> Vec<Port> {
+    let mut ports = service
+        .expose
+        .clone()
+        .unwrap_or_default()
+        .into_iter()
+        .chain(service.ports.clone().unwrap_or_default())
+        .collect::<Vec<_>>();
+
+    ports.sort_by_key(|port| port.0);
+    ports.dedup();
+
+    ports
+}
+
+/// The config new gateways start from, the rest is filled in by the guided prompts
+pub fn gateway_template(dep_name: &str, port: &Port) -> GatewayConfig {
+    GatewayConfig {
+        target_port: Some(port.0),
+        internal_domain: Some(format!("{dep_name}.hop")),
+        ..Default::default()
+    }
+}
+
+/// Applies the parts of a compose service that map to a deployment config
+/// on top of an existing deployment, everything else is left untouched
+pub fn apply_service_to_deployment(existing: &Deployment, service: &Service) -> CreateDeployment {
+    let mut config = CreateDeployment::from(existing.clone());
+    let compose: Deployment = service.clone().into();
+
+    // services with a build context do not have an image, the old one is kept
+    if !compose.config.image.name.is_empty() {
+        config.image = Some(compose.config.image);
+    }
+
+    config.env = compose.config.env;
+
+    if let Some(entrypoint) = compose.config.entrypoint {
+        config.entrypoint = Some(entrypoint);
+    }
+
+    if let Some(cmd) = compose.config.cmd {
+        config.command = Some(cmd);
+    }
+
+    if let Some(policy) = compose.config.restart_policy {
+        if !existing.is_ephemeral() {
+            config.restart_policy = Some(policy);
+        }
+    }
+
+    config
+}
+
+pub fn format_deployment_diff(old: &CreateDeployment, new: &CreateDeployment) -> Vec<String> {
+    let mut diff = vec![];
+
+    let old_image = old.image.clone().unwrap_or_default().name;
+    let new_image = new.image.clone().unwrap_or_default().name;
+
+    if old_image != new_image {
+        diff.push(changed_line("image", &old_image, &new_image));
+    }
+
+    let keys = old
+        .env
+        .keys()
+        .chain(new.env.keys())
+        .collect::<BTreeSet<_>>();
+
+    for key in keys {
+        match (old.env.get(key), new.env.get(key)) {
+            (Some(old_value), Some(new_value)) if old_value != new_value => {
+                diff.push(changed_line(&format!("env {key}"), old_value, new_value));
+            }
+
+            (None, Some(value)) => {
+                diff.push(style(format!("+ env {key}={value}")).green().to_string());
+            }
+
+            (Some(_), None) => {
+                diff.push(style(format!("- env {key}")).red().to_string());
+            }
+
+            _ => {}
+        }
+    }
+
+    if old.entrypoint != new.entrypoint {
+        diff.push(changed_line(
+            "entrypoint",
+            &format_shell_array(&old.entrypoint),
+            &format_shell_array(&new.entrypoint),
+        ));
+    }
+
+    if old.command != new.command {
+        diff.push(changed_line(
+            "command",
+            &format_shell_array(&old.command),
+            &format_shell_array(&new.command),
+        ));
+    }
+
+    if old.restart_policy != new.restart_policy {
+        diff.push(changed_line(
+            "restart policy",
+            &old.restart_policy.clone().unwrap_or_default().to_string(),
+            &new.restart_policy.clone().unwrap_or_default().to_string(),
+        ));
+    }
+
+    diff
+}
+
+fn changed_line(field: &str, old: &str, new: &str) -> String {
+    style(format!("~ {field}: {old} -> {new}"))
+        .yellow()
+        .to_string()
+}
+
+fn format_shell_array(array: &Option<Vec<String>>) -> String {
+    array
+        .as_ref()
+        .map(|array| serde_json::to_string(array).unwrap())
+        .unwrap_or_else(|| "None".to_string())
+}
+
+/// Health checks can't be updated, so this is used to decide if the existing
+/// one has to be replaced
+pub fn health_check_matches(existing: &HealthCheck, config: &CreateHealthCheck) -> bool {
+    existing.path == config.path
+        && existing.port == u64::from(config.port)
+        && existing.protocol == config.protocol
+        && existing.interval == config.interval
+        && existing.timeout == config.timeout
+        && existing.max_retries == config.max_retries
+        && existing.initial_delay == config.initial_delay
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::commands::containers::types::ContainerType;
+    use crate::commands::ignite::types::{Image, RestartPolicy};
+
+    struct Deps(Vec<&'static str>);
+
+    impl DependsOn for Deps {
+        fn depends_on(&self) -> Option<Vec<String>> {
+            Some(
+                self.0
+                    .iter()
+                    .map(std::string::ToString::to_string)
+                    .collect(),
+            )
+        }
+    }
+
+    fn order(services: &[(String, Deps)]) -> Result<Vec<String>> {
+        let mut services = services
+            .iter()
+            .map(|(name, deps)| (name, deps))
+            .collect::<Vec<_>>();
+
+        order_by_dependencies(&mut services)?;
+
+        Ok(services.into_iter().map(|(name, _)| name.clone()).collect())
+    }
+
+    #[test]
+    fn test_order_by_dependencies() {
+        let chain = vec![
+            ("web".to_string(), Deps(vec!["api"])),
+            ("api".to_string(), Deps(vec!["worker"])),
+            ("worker".to_string(), Deps(vec!["queue"])),
+            ("queue".to_string(), Deps(vec![])),
+        ];
+
+        assert_eq!(order(&chain).unwrap(), ["queue", "worker", "api", "web"]);
+
+        let independent = vec![
+            ("b".to_string(), Deps(vec![])),
+            ("a".to_string(), Deps(vec![])),
+            ("c".to_string(), Deps(vec!["a"])),
+        ];
+
+        assert_eq!(order(&independent).unwrap(), ["b", "a", "c"]);
+
+        let cycle = vec![
+            ("a".to_string(), Deps(vec!["b"])),
+            ("b".to_string(), Deps(vec!["a"])),
+            ("c".to_string(), Deps(vec![])),
+        ];
+
+        assert_eq!(
+            order(&cycle).unwrap_err().to_string(),
+            "Services `a`, `b` depend on each other in a cycle"
+        );
+
+        let unknown = vec![("a".to_string(), Deps(vec!["missing"]))];
+
+        assert_eq!(
+            order(&unknown).unwrap_err().to_string(),
+            "Service `a` depends on `missing`, which does not exist"
+        );
+    }
+
+    fn service(yaml: &str) -> Service {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    fn existing_deployment() -> Deployment {
+        let mut deployment = Deployment {
+            name: "api".to_string(),
+            ..Default::default()
+        };
+
+        deployment.config.image = Image {
+            name: "api:1".to_string(),
+        };
+        deployment.config.type_ = ContainerType::Persistent;
+        deployment.config.env = HashMap::from([
+            ("PORT".to_string(), "8080".to_string()),
+            ("OLD".to_string(), "1".to_string()),
+        ]);
+
+        deployment
+    }
+
+    #[test]
+    fn test_apply_service_to_deployment() {
+        let existing = existing_deployment();
+
+        let updated = apply_service_to_deployment(
+            &existing,
+            &service(
+                "image: api:2\nenvironment:\n  - PORT=3000\ncommand: [\"node\", \"index.js\"]\nrestart: on-failure\n",
+            ),
+        );
+
+        assert_eq!(updated.image.unwrap().name, "api:2");
+        assert_eq!(
+            updated.env,
+            HashMap::from([("PORT".to_string(), "3000".to_string())])
+        );
+        assert_eq!(
+            updated.command,
+            Some(vec!["node".to_string(), "index.js".to_string()])
+        );
+        assert_eq!(updated.restart_policy, Some(RestartPolicy::OnFailure));
+        assert_eq!(updated.name, Some("api".to_string()));
+
+        // services that are built keep the old image
+        let updated = apply_service_to_deployment(&existing, &service("build: .\n"));
+
+        assert_eq!(updated.image.unwrap().name, "api:1");
+    }
+
+    #[test]
+    fn test_format_deployment_diff() {
+        let old = CreateDeployment::from(existing_deployment());
+
+        assert!(format_deployment_diff(&old, &old).is_empty());
+
+        let mut new = old.clone();
+        new.image = Some(Image {
+            name: "api:2".to_string(),
+        });
+        new.env.remove("OLD");
+        new.env.insert("PORT".to_string(), "3000".to_string());
+        new.env.insert("NEW".to_string(), "yes".to_string());
+
+        let diff = format_deployment_diff(&old, &new)
+            .iter()
+            .map(|line| console::strip_ansi_codes(line).to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            diff,
+            vec![
+                "~ image: api:1 -> api:2",
+                "+ env NEW=yes",
+                "- env OLD",
+                "~ env PORT: 8080 -> 3000",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_health_check_matches() {
+        let existing: HealthCheck = serde_json::from_value(serde_json::json!({
+            "id": "health_check_1",
+            "deployment_id": "deployment_1",
+            "initial_delay": 5,
+            "interval": 60,
+            "max_retries": 3,
+            "path": "/",
+            "protocol": "HTTP",
+            "port": 8080,
+            "timeout": 50,
+            "success_threshold": 1,
+            "created_at": "2023-01-01T00:00:00Z",
+            "type": "liveness",
+        }))
+        .unwrap();
+
+        assert!(health_check_matches(
+            &existing,
+            &CreateHealthCheck::default()
+        ));
+
+        assert!(!health_check_matches(
+            &existing,
+            &CreateHealthCheck {
+                path: "/health".to_string(),
+                ..Default::default()
+            }
+        ));
+
+        assert!(!health_check_matches(
+            &existing,
+            &CreateHealthCheck {
+                port: 3000,
+                ..Default::default()
+            }
+        ));
+    }
+
+    #[test]
+    fn test_gateway_configs() {
+        let service = service("image: api\nexpose: [3000]\nports: [\"8080:3000\", 9090]\n");
+
+        let ports = service_ports(&service);
+
+        assert_eq!(ports, vec![Port(3000), Port(9090)]);
+
+        let config = gateway_template("api", &ports[0]);
+
+        assert_eq!(config.target_port, Some(3000));
+        assert_eq!(config.internal_domain.as_deref(), Some("api.hop"));
+        assert_eq!(config.type_, None);
+    }
+}
@@ -1,16 +1,23 @@
 use std::io::Write;
+use std::time::{Duration, Instant};
 
-use anyhow::Result;
+use anyhow::{bail, Result};
+use ms::{__to_string__, ms};
 use serde_json::Value;
 use tabwriter::TabWriter;
+use tokio::time::sleep;
 
 use super::types::{
     CreateHealthCheck, HealthCheck, HealthCheckState, MultipleHealthCheckState,
     MultipleHealthChecks, SingleHealthCheck,
 };
+use crate::commands::containers::types::{Container, ContainerState};
+use crate::commands::containers::utils::get_all_containers;
 use crate::state::http::HttpClient;
 use crate::utils::relative_time;
 
+const HEALTH_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
 pub fn create_health_check_config(
     config: super::create::HealthCheckCreate,
 ) -> Result<CreateHealthCheck> {
@@ -151,6 +158,95 @@ pub async fn get_health_state(
     Ok(state.health_check_states)
 }
 
+/// Polls the containers of a deployment matching `filter` until all of them pass their health checks
+pub async fn wait_for_healthy(
+    http: &HttpClient,
+    deployment_id: &str,
+    filter: impl Fn(&Container) -> bool,
+    timeout: Duration,
+) -> Result<()> {
+    // without health checks a running container is as healthy as it gets
+    let has_checks = !get_all_health_checks(http, deployment_id).await?.is_empty();
+
+    let deadline = Instant::now() + timeout;
+
+    log::info!("Waiting for the new containers to become healthy");
+
+    loop {
+        let containers = get_all_containers(http, deployment_id)
+            .await?
+            .into_iter()
+            .filter(&filter)
+            .collect::<Vec<_>>();
+
+        let states = if has_checks {
+            get_health_state(http, deployment_id).await?
+        } else {
+            vec![]
+        };
+
+        if !containers.is_empty() && containers_healthy(&containers, &states, has_checks)? {
+            log::info!("All {} new containers are healthy", containers.len());
+
+            return Ok(());
+        }
+
+        if Instant::now() >= deadline {
+            bail!(
+                "Containers did not become healthy within {}",
+                ms!(timeout.as_millis() as u64, true)
+            );
+        }
+
+        sleep(HEALTH_POLL_INTERVAL).await;
+    }
+}
+
+/// Whether all containers are running, errors once any of them failed or exited
+pub fn containers_running(containers: &[Container]) -> Result<bool> {
+    if let Some(container) = containers.iter().find(|container| {
+        matches!(
+            container.state,
+            ContainerState::Failed | ContainerState::Exited
+        )
+    }) {
+        bail!("Container `{}` is {}", container.id, container.state);
+    }
+
+    Ok(containers
+        .iter()
+        .all(|container| container.state == ContainerState::Running))
+}
+
+/// Whether all containers are running and passing their health checks, errors once any of them failed
+pub fn containers_healthy(
+    containers: &[Container],
+    states: &[HealthCheckState],
+    has_checks: bool,
+) -> Result<bool> {
+    let running = containers_running(containers)?;
+
+    if let Some(state) = states.iter().find(|state| {
+        state.state == "unhealthy"
+            && containers
+                .iter()
+                .any(|container| container.id == state.container_id)
+    }) {
+        bail!("Container `{}` failed its health check", state.container_id);
+    }
+
+    Ok(running
+        && (!has_checks
+            || containers.iter().all(|container| {
+                let mut checks = states
+                    .iter()
+                    .filter(|state| state.container_id == container.id)
+                    .peekable();
+
+                checks.peek().is_some() && checks.all(|state| state.state == "healthy")
+            })))
+}
+
 pub fn format_health_checks(checks: &[HealthCheck], title: bool) -> Vec<String> {
     let mut tw = TabWriter::new(vec![]);
 
@@ -210,3 +306,79 @@ pub fn format_health_state(state: &[HealthCheckState], title: bool) -> Vec<Strin
         .map(std::string::ToString::to_string)
         .collect()
 }
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    fn container(id: &str, state: &str) -> Container {
+        serde_json::from_value(json!({
+            "id": id,
+            "created_at": "2023-01-01T00:00:00Z",
+            "state": state,
+            "deployment_id": "deployment_1",
+            "region": "us-east-1",
+            "type": "persistent",
+        }))
+        .unwrap()
+    }
+
+    fn health_state(container_id: &str, state: &str) -> HealthCheckState {
+        serde_json::from_value(json!({
+            "state": state,
+            "container_id": container_id,
+            "health_check_id": "health_check_1",
+            "deployment_id": "deployment_1",
+            "created_at": "2023-01-01T00:00:00Z",
+            "next_check": "2023-01-01T00:01:00Z",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_containers_running() {
+        let running = container("container_1", "running");
+        let pending = container("container_2", "pending");
+        let failed = container("container_3", "failed");
+
+        assert!(containers_running(std::slice::from_ref(&running)).unwrap());
+        assert!(!containers_running(&[running.clone(), pending]).unwrap());
+        assert_eq!(
+            containers_running(&[running, failed])
+                .unwrap_err()
+                .to_string(),
+            "Container `container_3` is failed"
+        );
+    }
+
+    #[test]
+    fn test_containers_healthy() {
+        let containers = [container("container_1", "running")];
+
+        assert!(containers_healthy(&containers, &[], false).unwrap());
+        // the first check has not run yet
+        assert!(!containers_healthy(&containers, &[], true).unwrap());
+        assert!(
+            !containers_healthy(&containers, &[health_state("container_1", "pending")], true)
+                .unwrap()
+        );
+        assert!(
+            containers_healthy(&containers, &[health_state("container_1", "healthy")], true)
+                .unwrap()
+        );
+        assert!(containers_healthy(
+            &containers,
+            &[health_state("container_1", "unhealthy")],
+            true
+        )
+        .is_err());
+        assert!(!containers_healthy(
+            &[container("container_2", "pending")],
+            &[health_state("container_2", "healthy")],
+            true
+        )
+        .unwrap());
+    }
+}
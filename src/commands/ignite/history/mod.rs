@@ -0,0 +1,53 @@
+pub mod types;
+pub mod utils;
+
+use anyhow::Result;
+use clap::Parser;
+
+use self::utils::{build_timeline, format_timeline};
+use crate::commands::ignite::builds::utils::get_all_builds;
+use crate::state::State;
+use crate::store::history::DeploymentHistory;
+use crate::store::Store;
+
+#[derive(Debug, Parser)]
+#[clap(about = "Show the timeline of builds, rollouts and changes of a deployment")]
+#[group(skip)]
+pub struct Options {
+    #[clap(help = "Name or ID of the deployment")]
+    pub deployment: Option<String>,
+
+    #[clap(long, help = "Print the timeline as JSON")]
+    pub json: bool,
+}
+
+pub async fn handle(options: Options, state: State) -> Result<()> {
+    let deployment = state
+        .get_deployment_by_opt_name_or_id(options.deployment.as_deref())
+        .await?;
+
+    let builds = get_all_builds(&state.http, &deployment.id).await?;
+
+    let mut history = DeploymentHistory::new().await?;
+
+    let entries = history
+        .deployments
+        .remove(&deployment.id)
+        .unwrap_or_default();
+
+    let events = build_timeline(&deployment, &builds, &entries, history.salt());
+
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&events)?);
+
+        return Ok(());
+    }
+
+    println!("{}", format_timeline(&events, true).join("\n"));
+
+    if entries.is_empty() {
+        log::info!("Rollouts, promotions, scaling and config changes are only tracked when made from this machine");
+    }
+
+    Ok(())
+}
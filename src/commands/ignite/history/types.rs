@@ -0,0 +1,34 @@
+use std::fmt::Display;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EventKind {
+    Created,
+    Build,
+    Rollout,
+    Promote,
+    Scale,
+    Config,
+}
+
+impl Display for EventKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            serde_json::to_string(self).unwrap().replace('"', "")
+        )
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct TimelineEvent {
+    pub at: DateTime<Utc>,
+    pub kind: EventKind,
+    /// only known for changes made from this machine
+    pub by: Option<String>,
+    pub description: String,
+}
@@ -0,0 +1,327 @@
+use std::io::Write;
+
+use anyhow::Result;
+use chrono::{DateTime, Local, Utc};
+use serde_json::Value;
+use sha1::{Digest, Sha1};
+use tabwriter::TabWriter;
+
+use super::types::{EventKind, TimelineEvent};
+use crate::commands::ignite::builds::types::Build;
+use crate::commands::ignite::diff::utils::MASK;
+use crate::commands::ignite::types::{Config, Deployment};
+use crate::commands::secrets::utils::get_secret_name;
+use crate::state::State;
+use crate::store::history::{Change, DeploymentHistory, HistoryEntry};
+use crate::store::Store;
+
+const FINGERPRINT_LENGTH: usize = 12;
+
+/// Saves a change made to a deployment, call it after the change went through
+///
+/// The change already happened, so failing to save it only logs a warning
+pub async fn record_change(state: &State, deployment_id: &str, change: Change) {
+    if let Err(error) = save_change(state, deployment_id, change).await {
+        log::warn!("Could not save the change to the deployment history: {error}");
+    }
+}
+
+async fn save_change(state: &State, deployment_id: &str, change: Change) -> Result<()> {
+    let mut history = DeploymentHistory::new().await?;
+
+    // env values are fingerprinted so no plaintext ends up on disk
+    let change = match change {
+        Change::Config { before } => Change::Config {
+            before: Box::new(fingerprint_config(&before, history.salt())),
+        },
+        change => change,
+    };
+
+    history.push(
+        deployment_id,
+        HistoryEntry {
+            at: Utc::now(),
+            by: state.actor(),
+            change,
+        },
+    );
+
+    history.save().await?;
+
+    Ok(())
+}
+
+/// Merges the builds from the api with the changes made from this machine, oldest first
+pub fn build_timeline(
+    deployment: &Deployment,
+    builds: &[Build],
+    entries: &[HistoryEntry],
+    salt: &str,
+) -> Vec<TimelineEvent> {
+    let mut events = vec![];
+
+    if let Ok(created_at) = DateTime::parse_from_rfc3339(&deployment.created_at) {
+        events.push(TimelineEvent {
+            at: created_at.with_timezone(&Utc),
+            kind: EventKind::Created,
+            by: None,
+            description: format!("Deployment `{}` created", deployment.name),
+        });
+    }
+
+    for build in builds {
+        events.push(TimelineEvent {
+            at: build.started_at,
+            kind: EventKind::Build,
+            by: None,
+            description: format!("Build `{}` {} ({})", build.id, build.state, build.method),
+        });
+    }
+
+    for (idx, entry) in entries.iter().enumerate() {
+        let (kind, description) = match &entry.change {
+            Change::Config { before } => {
+                // the next snapshot is what this change left behind
+                let next = entries[idx + 1..]
+                    .iter()
+                    .find_map(|entry| match &entry.change {
+                        Change::Config { before } => Some(before.as_ref()),
+                        _ => None,
+                    });
+
+                let fields = match next {
+                    Some(after) => changed_fields(before, after),
+                    // the current config is fingerprinted like the snapshots to compare them
+                    None => changed_fields(before, &fingerprint_config(&deployment.config, salt)),
+                };
+
+                let description = if fields.is_empty() {
+                    "Config updated without changes".to_string()
+                } else {
+                    format!("Config changed: {}", fields.join(", "))
+                };
+
+                (EventKind::Config, description)
+            }
+
+            Change::Scale { from, to } => (
+                EventKind::Scale,
+                format!("Scaled from {from} to {to} containers"),
+            ),
+
+            Change::Rollout {
+                rollout_id: Some(rollout_id),
+                ..
+            } => (
+                EventKind::Rollout,
+                format!("Rollout `{rollout_id}` started"),
+            ),
+
+            Change::Rollout {
+                rollout_id: None,
+                canary,
+            } => (
+                EventKind::Rollout,
+                match canary {
+                    Some(percentage) => {
+                        format!("Canary rollout to {percentage}% of the containers, then the rest")
+                    }
+                    None => "Rollout started".to_string(),
+                },
+            ),
+
+            Change::Promote { build_id } => {
+                (EventKind::Promote, format!("Build `{build_id}` promoted"))
+            }
+        };
+
+        events.push(TimelineEvent {
+            at: entry.at,
+            kind,
+            by: Some(entry.by.clone()),
+            description,
+        });
+    }
+
+    events.sort_by_key(|event| event.at);
+
+    events
+}
+
+/// Replaces the env values that don't reference a secret with a salted hash,
+/// enough to tell when a value changed without storing it
+pub fn fingerprint_config(config: &Config, salt: &str) -> Config {
+    let mut config = config.clone();
+
+    for value in config.env.values_mut() {
+        if get_secret_name(value).is_none() {
+            let hash = format!("{:x}", Sha1::digest(format!("{salt}:{value}")));
+
+            *value = format!("{MASK}{}", &hash[..FINGERPRINT_LENGTH]);
+        }
+    }
+
+    config
+}
+
+/// Names of the config fields that differ, env variables are listed one by one
+pub fn changed_fields(before: &Config, after: &Config) -> Vec<String> {
+    let (Ok(Value::Object(before_fields)), Ok(Value::Object(after_fields))) =
+        (serde_json::to_value(before), serde_json::to_value(after))
+    else {
+        return vec![];
+    };
+
+    let mut fields = before_fields
+        .iter()
+        .filter(|(key, value)| *key != "env" && after_fields.get(*key) != Some(*value))
+        .map(|(key, _)| key.clone())
+        .collect::<Vec<_>>();
+
+    let mut env = before
+        .env
+        .iter()
+        .filter_map(|(key, value)| match after.env.get(key) {
+            None => Some(format!("env.{key} (removed)")),
+            Some(new) if new != value => Some(format!("env.{key} (changed)")),
+            _ => None,
+        })
+        .chain(
+            after
+                .env
+                .keys()
+                .filter(|key| !before.env.contains_key(*key))
+                .map(|key| format!("env.{key} (added)")),
+        )
+        .collect::<Vec<_>>();
+
+    env.sort();
+    fields.extend(env);
+
+    fields
+}
+
+pub fn format_timeline(events: &[TimelineEvent], title: bool) -> Vec<String> {
+    let mut tw = TabWriter::new(vec![]);
+
+    if title {
+        writeln!(&mut tw, "TIME\tEVENT\tBY\tDESCRIPTION").unwrap();
+    }
+
+    for event in events {
+        writeln!(
+            &mut tw,
+            "{}\t{}\t{}\t{}",
+            event.at.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S"),
+            event.kind,
+            event.by.as_deref().unwrap_or("-"),
+            event.description,
+        )
+        .unwrap();
+    }
+
+    String::from_utf8(tw.into_inner().unwrap())
+        .unwrap()
+        .lines()
+        .map(std::string::ToString::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn entry(at: i64, change: Change) -> HistoryEntry {
+        HistoryEntry {
+            at: Utc.timestamp_opt(at, 0).unwrap(),
+            by: "user@hop.io".to_string(),
+            change,
+        }
+    }
+
+    #[test]
+    fn test_changed_fields() {
+        let mut before = Config::default();
+        before.env.insert("KEPT".to_string(), "1".to_string());
+        before.env.insert("CHANGED".to_string(), "1".to_string());
+        before.env.insert("REMOVED".to_string(), "1".to_string());
+
+        let mut after = before.clone();
+        after.image.name = "hop/app:new".to_string();
+        after.env.insert("CHANGED".to_string(), "2".to_string());
+        after.env.remove("REMOVED");
+        after.env.insert("ADDED".to_string(), "1".to_string());
+
+        assert_eq!(
+            changed_fields(&before, &after),
+            vec![
+                "image",
+                "env.ADDED (added)",
+                "env.CHANGED (changed)",
+                "env.REMOVED (removed)"
+            ]
+        );
+
+        assert!(changed_fields(&before, &before).is_empty());
+
+        // snapshots are stored fingerprinted, changed values still show up
+        assert_eq!(
+            changed_fields(
+                &fingerprint_config(&before, "salt"),
+                &fingerprint_config(&after, "salt")
+            ),
+            changed_fields(&before, &after)
+        );
+    }
+
+    #[test]
+    fn test_fingerprint_config() {
+        let mut config = Config::default();
+        config
+            .env
+            .insert("PASSWORD".to_string(), "hunter2".to_string());
+        config
+            .env
+            .insert("DB".to_string(), "${secrets.DB_URL}".to_string());
+
+        let fingerprinted = fingerprint_config(&config, "salt");
+
+        assert!(fingerprinted.env["PASSWORD"].starts_with(MASK));
+        assert!(!fingerprinted.env["PASSWORD"].contains("hunter2"));
+        assert_eq!(fingerprinted.env["DB"], "${secrets.DB_URL}");
+        assert_eq!(fingerprint_config(&config, "salt"), fingerprinted);
+        assert_ne!(fingerprint_config(&config, "other"), fingerprinted);
+    }
+
+    #[test]
+    fn test_build_timeline() {
+        let mut deployment = Deployment {
+            name: "app".to_string(),
+            created_at: "1970-01-01T00:00:10Z".to_string(),
+            ..Default::default()
+        };
+        deployment.config.cmd = Some(vec!["start".to_string()]);
+
+        let entries = vec![
+            entry(
+                20,
+                Change::Config {
+                    before: Box::default(),
+                },
+            ),
+            entry(30, Change::Scale { from: 1, to: 3 }),
+        ];
+
+        let events = build_timeline(&deployment, &[], &entries, "salt");
+
+        assert_eq!(
+            events.iter().map(|e| e.kind).collect::<Vec<_>>(),
+            vec![EventKind::Created, EventKind::Config, EventKind::Scale]
+        );
+        // the last config change is compared against the current config
+        assert_eq!(events[1].description, "Config changed: cmd");
+        assert_eq!(events[2].by.as_deref(), Some("user@hop.io"));
+    }
+}
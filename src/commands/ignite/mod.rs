@@ -1,14 +1,20 @@
 pub mod builds;
+pub mod clone;
 pub mod create;
 mod delete;
+pub mod diff;
+pub mod env;
+mod export;
 pub mod from_compose;
 mod get_env;
 pub mod groups;
-mod health;
+pub mod health;
+pub mod history;
 mod inspect;
 mod list;
 mod promote;
 pub mod rollout;
+mod run_local;
 mod scale;
 mod templates;
 pub mod types;
@@ -34,12 +40,20 @@ pub enum Commands {
     #[clap(alias = "rollouts")]
     Rollout(rollout::Options),
     Scale(scale::Options),
+    #[clap(name = "run-local")]
+    RunLocal(run_local::Options),
+    #[clap(alias = "copy")]
+    Clone(clone::Options),
     #[clap(name = "get-env")]
     GetEnv(get_env::Options),
+    Env(env::Options),
     #[clap(alias = "compose")]
     FromCompose(from_compose::Options),
+    Export(export::Options),
+    Diff(diff::Options),
     #[clap(alias = "check")]
     Health(health::Options),
+    History(history::Options),
     #[clap(alias = "build")]
     Builds(builds::Options),
     #[clap(alias = "gr")]
@@ -73,13 +87,19 @@ pub async fn handle(options: Options, state: State) -> Result<()> {
         Commands::Inspect(options) => inspect::handle(options, state).await,
         Commands::Rollout(options) => rollout::handle(options, state).await,
         Commands::Scale(options) => scale::handle(options, state).await,
+        Commands::RunLocal(options) => run_local::handle(options, state).await,
+        Commands::Clone(options) => clone::handle(options, state).await,
         Commands::GetEnv(options) => get_env::handle(options, state).await,
+        Commands::Env(options) => env::handle(options, state).await,
         Commands::Health(options) => health::handle(options, state).await,
+        Commands::History(options) => history::handle(options, state).await,
         Commands::Containers(options) => super::containers::handle(options, state).await,
         Commands::Gateways(options) => super::gateways::handle(options, state).await,
         Commands::Promote(options) => promote::handle(options, state).await,
         Commands::Builds(options) => builds::handle(options, state).await,
         Commands::FromCompose(options) => from_compose::handle(options, state).await,
+        Commands::Export(options) => export::handle(options, state).await,
+        Commands::Diff(options) => diff::handle(options, state).await,
         Commands::Tunnel(options) => super::tunnel::handle(&options, state).await,
         Commands::Templates(options) => templates::handle(options, state).await,
         Commands::Groups(options) => groups::handle(options, state).await,
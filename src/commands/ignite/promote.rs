@@ -5,7 +5,9 @@ use super::utils::promote;
 use crate::commands::ignite::builds::types::BuildState;
 use crate::commands::ignite::builds::utils::get_all_builds;
 use crate::commands::ignite::groups::utils::fetch_grouped_deployments;
+use crate::commands::ignite::history::utils::record_change;
 use crate::state::State;
+use crate::store::history::Change;
 
 #[derive(Debug, Parser)]
 #[clap(about = "Rollback containers in a deployment")]
@@ -68,6 +70,8 @@ pub async fn handle(options: Options, state: State) -> Result<()> {
 
     promote(&state.http, &deployment_id, &build_id).await?;
 
+    record_change(&state, &deployment_id, Change::Promote { build_id }).await;
+
     log::info!("Rolling out new containers");
 
     Ok(())
@@ -1,19 +1,82 @@
-use anyhow::Result;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, ensure, Result};
 use clap::Parser;
+use ms::{__to_string__, ms};
+use tokio::time::sleep;
 
-use super::utils::rollout;
+use super::utils::{get_deployment, rollout};
+use crate::commands::containers::utils::{
+    create_containers, delete_container, error_rate, get_all_containers, get_container_logs,
+};
+use crate::commands::ignite::health::utils::{
+    containers_healthy, get_all_health_checks, get_health_state, wait_for_healthy,
+};
+use crate::commands::ignite::history::utils::record_change;
+use crate::commands::ignite::types::Deployment;
+use crate::store::history::Change;
+use crate::utils::parse_duration;
 use crate::{commands::ignite::groups::utils::fetch_grouped_deployments, state::State};
 
+const BAKE_POLL_INTERVAL: Duration = Duration::from_secs(5);
+// logs fetched per canary container on every check
+const CANARY_LOG_LINES: u64 = 200;
+// too few lines make the error rate meaningless
+const MIN_LOG_LINES: usize = 20;
+
 #[derive(Debug, Parser)]
 #[clap(about = "Rollout new containers to a deployment")]
 #[group(skip)]
 pub struct Options {
     #[clap(help = "ID of the deployment")]
     pub deployment: Option<String>,
+
+    #[clap(
+        long,
+        help = "Roll out to this percentage of containers first and watch them before continuing",
+        value_parser = clap::value_parser!(u8).range(1..=100)
+    )]
+    pub canary: Option<u8>,
+
+    #[clap(
+        long,
+        help = "How long to watch the canary containers",
+        default_value = "2m",
+        value_parser = parse_duration
+    )]
+    pub bake: Duration,
+
+    #[clap(
+        long,
+        help = "Abort the canary when more than this percentage of its log lines are errors",
+        default_value = "10",
+        value_parser = parse_percentage
+    )]
+    pub max_error_rate: f64,
+
+    #[clap(
+        long,
+        help = "Count lines logged to stderr as errors, many runtimes log everything there"
+    )]
+    pub count_stderr: bool,
+
+    #[clap(
+        long,
+        help = "Containers to replace at once after the canary, defaults to the canary size"
+    )]
+    pub step: Option<usize>,
+
+    #[clap(
+        long,
+        help = "How long to wait for each step to become healthy",
+        default_value = "5m",
+        value_parser = parse_duration
+    )]
+    pub timeout: Duration,
 }
 
 pub async fn handle(options: Options, state: State) -> Result<()> {
-    let deployment_id = match options.deployment {
+    let deployment_id = match options.deployment.clone() {
         Some(id) => id,
 
         None => {
@@ -38,9 +101,267 @@ pub async fn handle(options: Options, state: State) -> Result<()> {
         }
     };
 
-    rollout(&state.http, &deployment_id).await?;
+    if let Some(percentage) = options.canary {
+        let deployment = get_deployment(&state.http, &deployment_id).await?;
+
+        canary_rollout(&state, &deployment, percentage, &options).await?;
+
+        record_change(
+            &state,
+            &deployment_id,
+            Change::Rollout {
+                rollout_id: None,
+                canary: Some(percentage),
+            },
+        )
+        .await;
+
+        return Ok(());
+    }
+
+    let rollout = rollout(&state.http, &deployment_id).await?;
+
+    record_change(
+        &state,
+        &deployment_id,
+        Change::Rollout {
+            rollout_id: Some(rollout.id),
+            canary: None,
+        },
+    )
+    .await;
 
     log::info!("Rolling out new containers");
 
     Ok(())
 }
+
+/// Replaces a share of the containers first, watches them for the bake period
+/// and then replaces the rest in steps
+async fn canary_rollout(
+    state: &State,
+    deployment: &Deployment,
+    percentage: u8,
+    options: &Options,
+) -> Result<()> {
+    ensure!(
+        deployment.can_rollout() && deployment.can_scale(),
+        "Canary rollouts need a manually scaled, non stateful deployment"
+    );
+
+    let old = get_all_containers(&state.http, &deployment.id)
+        .await?
+        .into_iter()
+        .map(|container| container.id)
+        .collect::<Vec<_>>();
+
+    ensure!(!old.is_empty(), "No containers to roll out to");
+
+    let canary_count = canary_size(old.len(), percentage);
+
+    log::info!(
+        "Creating {canary_count} canary containers next to the {} existing ones",
+        old.len()
+    );
+
+    let canaries = create_containers(&state.http, &deployment.id, canary_count as u64)
+        .await?
+        .into_iter()
+        .map(|container| container.id)
+        .collect::<Vec<_>>();
+
+    if let Err(error) = bake(state, deployment, &canaries, options).await {
+        log::error!("Canary failed: {error}");
+        log::info!("Removing the canary containers");
+
+        for id in &canaries {
+            delete_container(&state.http, id, false).await?;
+        }
+
+        bail!(
+            "Canary rollout aborted, the old containers were left untouched. Run `hop ignite promote {}` to pin a previous build",
+            deployment.id
+        );
+    }
+
+    log::info!("Canary is healthy, continuing the rollout");
+
+    let mut completed = 0;
+
+    if let Err(error) = replace_old_containers(
+        state,
+        deployment,
+        &old,
+        canary_count,
+        options,
+        &mut completed,
+    )
+    .await
+    {
+        // whatever still has an old id was not replaced yet
+        let left = get_all_containers(&state.http, &deployment.id)
+            .await
+            .map(|containers| {
+                containers
+                    .into_iter()
+                    .map(|container| container.id)
+                    .filter(|id| old.contains(id))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        bail!(
+            "Rollout stopped after {completed} of {} steps: {error}\n{} containers still run the old build: {}\nRun `hop ignite promote {}` to pin a build for all of them",
+            replace_steps(&old, canary_count, options),
+            left.len(),
+            left.join(", "),
+            deployment.id
+        );
+    }
+
+    log::info!("Rolled out to all {} containers", old.len());
+
+    Ok(())
+}
+
+/// Steps to replace the containers left after the canary
+fn replace_steps(old: &[String], canary_count: usize, options: &Options) -> usize {
+    let remaining = old.len().saturating_sub(canary_count);
+
+    remaining.div_ceil(options.step.unwrap_or(canary_count).max(1))
+}
+
+/// Removes the containers the canaries took the place of and recreates the rest in steps
+async fn replace_old_containers(
+    state: &State,
+    deployment: &Deployment,
+    old: &[String],
+    canary_count: usize,
+    options: &Options,
+    completed: &mut usize,
+) -> Result<()> {
+    // the canaries take the place of the first containers
+    let (replaced, remaining) = old.split_at(canary_count.min(old.len()));
+
+    for id in replaced {
+        delete_container(&state.http, id, false).await?;
+    }
+
+    let step = options.step.unwrap_or(canary_count).max(1);
+
+    for (idx, batch) in remaining.chunks(step).enumerate() {
+        log::info!(
+            "Replacing {} containers, step {}/{}",
+            batch.len(),
+            idx + 1,
+            replace_steps(old, canary_count, options)
+        );
+
+        let mut recreated = vec![];
+
+        for id in batch {
+            if let Some(container) = delete_container(&state.http, id, true).await? {
+                recreated.push(container.id);
+            }
+        }
+
+        wait_for_healthy(
+            &state.http,
+            &deployment.id,
+            |container| recreated.contains(&container.id),
+            options.timeout,
+        )
+        .await?;
+
+        *completed += 1;
+    }
+
+    Ok(())
+}
+
+fn parse_percentage(value: &str) -> Result<f64> {
+    let percentage = value.parse::<f64>()?;
+
+    ensure!(
+        (0.0..=100.0).contains(&percentage),
+        "has to be between 0 and 100"
+    );
+
+    Ok(percentage)
+}
+
+/// Watches the health and error rate of the canary containers until the bake period is over
+async fn bake(
+    state: &State,
+    deployment: &Deployment,
+    canaries: &[String],
+    options: &Options,
+) -> Result<()> {
+    let has_checks = !get_all_health_checks(&state.http, &deployment.id)
+        .await?
+        .is_empty();
+
+    let started = Instant::now();
+    let started_at = chrono::Utc::now();
+
+    log::info!(
+        "Watching the canary for {}",
+        ms!(options.bake.as_millis() as u64, true)
+    );
+
+    loop {
+        let containers = get_all_containers(&state.http, &deployment.id)
+            .await?
+            .into_iter()
+            .filter(|container| canaries.contains(&container.id))
+            .collect::<Vec<_>>();
+
+        ensure!(
+            containers.len() == canaries.len(),
+            "Some of the canary containers are gone"
+        );
+
+        let states = if has_checks {
+            get_health_state(&state.http, &deployment.id).await?
+        } else {
+            vec![]
+        };
+
+        let healthy = containers_healthy(&containers, &states, has_checks)?;
+
+        let mut logs = vec![];
+
+        for id in canaries {
+            logs.extend(
+                get_container_logs(&state.http, id, CANARY_LOG_LINES, "desc")
+                    .await?
+                    .into_iter()
+                    .filter(|log| log.timestamp >= started_at),
+            );
+        }
+
+        let rate = error_rate(&logs, options.count_stderr) * 100.0;
+
+        log::debug!("Canary error rate: {rate:.1}% of {} lines", logs.len());
+
+        if logs.len() >= MIN_LOG_LINES && rate > options.max_error_rate {
+            bail!(
+                "{rate:.1}% of the canary log lines are errors, the limit is {}%",
+                options.max_error_rate
+            );
+        }
+
+        if started.elapsed() >= options.bake {
+            ensure!(healthy, "Canary containers did not become healthy");
+
+            return Ok(());
+        }
+
+        sleep(BAKE_POLL_INTERVAL).await;
+    }
+}
+
+/// Number of canary containers for a percentage, at least one
+fn canary_size(containers: usize, percentage: u8) -> usize {
+    (containers * percentage as usize).div_ceil(100).max(1)
+}
@@ -0,0 +1,244 @@
+use std::collections::HashMap;
+use std::env::temp_dir;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, ensure, Context, Result};
+use async_compression::tokio::bufread::GzipDecoder;
+use clap::Parser;
+use tokio::fs;
+use tokio_tar::Archive;
+
+use super::types::Deployment;
+use super::utils::env_file_to_map;
+use crate::commands::deploy::local::types::RunOptions;
+use crate::commands::deploy::local::{pull_image, run_image};
+use crate::commands::gateways::util::get_all_gateways;
+use crate::commands::secrets::utils::get_secret_name;
+use crate::commands::volumes::copy::fslike::FsLike;
+use crate::state::State;
+use crate::utils::size::parse_size;
+
+#[derive(Debug, Parser)]
+#[clap(about = "Run the image of a deployment locally with its config")]
+#[group(skip)]
+pub struct Options {
+    #[clap(help = "Name or ID of the deployment")]
+    pub deployment: Option<String>,
+
+    #[clap(
+        long,
+        help = "Local directory or backup (.tar.gz) to mount as the volume"
+    )]
+    pub volume: Option<PathBuf>,
+
+    #[clap(
+        long,
+        help = "Download the current volume of the deployment and mount it"
+    )]
+    pub backup: bool,
+
+    #[clap(
+        long,
+        help = "File with the values of the secrets in the form of NAME=VALUE"
+    )]
+    pub secrets_file: Option<PathBuf>,
+
+    #[clap(long, help = "Do not limit the cpu and memory of the container")]
+    pub no_limits: bool,
+
+    #[clap(short, long, help = "Skip the confirmation for resolving secrets")]
+    pub yes: bool,
+}
+
+pub async fn handle(options: Options, state: State) -> Result<()> {
+    ensure!(
+        options.volume.is_none() || !options.backup,
+        "`--volume` and `--backup` can not be used together"
+    );
+
+    let deployment = state
+        .get_deployment_by_opt_name_or_id(options.deployment.as_deref())
+        .await?;
+
+    let env = resolve_env(&deployment, &options).await?;
+
+    let mut ports = get_all_gateways(&state.http, &deployment.id)
+        .await?
+        .into_iter()
+        .filter_map(|gateway| gateway.target_port)
+        .collect::<Vec<_>>();
+
+    ports.sort_unstable();
+    ports.dedup();
+
+    let volume = match &deployment.config.volume {
+        Some(volume) => {
+            let dir = if options.backup {
+                Some(download_volume(&state, &deployment).await?)
+            } else if let Some(path) = &options.volume {
+                Some(prepare_volume(&deployment, path).await?)
+            } else {
+                log::warn!(
+                    "No `--volume` or `--backup` given, `{}` will be empty",
+                    volume.mount_path
+                );
+
+                None
+            };
+
+            dir.map(|dir| (dir, volume.mount_path.clone()))
+        }
+
+        None => {
+            if options.volume.is_some() || options.backup {
+                bail!("Deployment `{}` does not have a volume", deployment.name);
+            }
+
+            None
+        }
+    };
+
+    let (cpus, memory) = if options.no_limits {
+        (None, None)
+    } else {
+        (
+            Some(deployment.config.resources.vcpu),
+            Some(parse_size(&deployment.config.resources.ram)?),
+        )
+    };
+
+    let image = &deployment.config.image.name;
+
+    log::info!("Pulling `{image}`");
+
+    let engine = pull_image(&state, image).await?;
+
+    for port in &ports {
+        log::info!("Publishing port {port}");
+    }
+
+    log::info!("Running `{}` locally", deployment.name);
+
+    run_image(
+        engine,
+        image,
+        &RunOptions {
+            env,
+            ports,
+            entrypoint: deployment.config.entrypoint.clone(),
+            cmd: deployment.config.cmd.clone(),
+            cpus,
+            memory,
+            volume,
+        },
+    )
+    .await
+}
+
+/// Env of the deployment with the secret references resolved from a file or prompts,
+/// only after the user agreed to it
+async fn resolve_env(
+    deployment: &Deployment,
+    options: &Options,
+) -> Result<HashMap<String, String>> {
+    let (secrets, mut env): (HashMap<_, _>, HashMap<_, _>) = deployment
+        .config
+        .env
+        .iter()
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .partition(|(_, value)| get_secret_name(value).is_some());
+
+    if secrets.is_empty() {
+        return Ok(env);
+    }
+
+    let mut names = secrets.keys().cloned().collect::<Vec<_>>();
+    names.sort();
+
+    log::info!("The deployment uses secrets for {}", names.join(", "));
+
+    if !options.yes
+        && !dialoguer::Confirm::new()
+            .with_prompt("Resolve the secrets and pass them to the local container?")
+            .default(false)
+            .interact_opt()?
+            .unwrap_or(false)
+    {
+        log::warn!("Running without the secrets");
+
+        return Ok(env);
+    }
+
+    // secret values can not be read back from Hop, so they come from the user
+    let file = match &options.secrets_file {
+        Some(path) => env_file_to_map(path.clone()).await?,
+        None => HashMap::new(),
+    };
+
+    for (key, value) in secrets {
+        let name = get_secret_name(&value).unwrap();
+
+        let resolved = match file.get(&name).or_else(|| file.get(&key)) {
+            Some(resolved) => resolved.clone(),
+
+            None => dialoguer::Password::new()
+                .with_prompt(format!("Value of secret `{name}` for `{key}`"))
+                .allow_empty_password(true)
+                .interact()?,
+        };
+
+        if resolved.is_empty() {
+            log::warn!("Skipping `{key}`, no value for secret `{name}`");
+
+            continue;
+        }
+
+        env.insert(key, resolved);
+    }
+
+    Ok(env)
+}
+
+/// Uses a local directory as is or unpacks a backup into a temporary one
+async fn prepare_volume(deployment: &Deployment, path: &Path) -> Result<PathBuf> {
+    if path.is_dir() {
+        return path.canonicalize().context("Could not get canonical path");
+    }
+
+    ensure!(path.is_file(), "{} does not exist", path.display());
+
+    let data = fs::read(path)
+        .await
+        .with_context(|| format!("Could not read {}", path.display()))?;
+
+    unpack_volume(deployment, &data).await
+}
+
+async fn download_volume(state: &State, deployment: &Deployment) -> Result<PathBuf> {
+    log::info!("Downloading the volume of `{}`", deployment.name);
+
+    let source = FsLike::from_str(state, &format!("{}:/", deployment.name)).await?;
+
+    let (_, data) = source.read().await?;
+
+    unpack_volume(deployment, &data).await
+}
+
+async fn unpack_volume(deployment: &Deployment, data: &[u8]) -> Result<PathBuf> {
+    let dir = temp_dir().join(format!("hop_run-local_{}", deployment.name));
+
+    if dir.exists() {
+        fs::remove_dir_all(&dir).await?;
+    }
+
+    fs::create_dir_all(&dir).await?;
+
+    Archive::new(GzipDecoder::new(data))
+        .unpack(&dir)
+        .await
+        .context("Could not unpack the volume backup")?;
+
+    log::info!("Unpacked the volume to {}", dir.display());
+
+    Ok(dir)
+}
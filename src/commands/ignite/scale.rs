@@ -3,8 +3,10 @@ use clap::Parser;
 
 use super::utils::scale;
 use crate::commands::ignite::groups::utils::fetch_grouped_deployments;
+use crate::commands::ignite::history::utils::record_change;
 use crate::commands::ignite::utils::get_deployment;
 use crate::state::State;
+use crate::store::history::Change;
 
 #[derive(Debug, Parser)]
 #[clap(about = "Scale a deployment")]
@@ -53,6 +55,16 @@ pub async fn handle(options: Options, state: State) -> Result<()> {
 
     scale(&state.http, &deployment.id, scale_count).await?;
 
+    record_change(
+        &state,
+        &deployment.id,
+        Change::Scale {
+            from: deployment.container_count,
+            to: scale_count,
+        },
+    )
+    .await;
+
     log::info!("Scaling deployment to {} containers", scale_count);
 
     Ok(())
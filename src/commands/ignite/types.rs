@@ -481,6 +481,8 @@ pub enum RolloutEvents {
 pub struct RolloutEvent {
     pub id: String,
     pub state: RolloutState,
+    #[serde(default)]
+    pub deployment_id: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
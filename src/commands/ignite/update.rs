@@ -3,8 +3,10 @@ use clap::Parser;
 
 use super::create::Options as CreateOptions;
 use crate::commands::ignite::groups::utils::fetch_grouped_deployments;
+use crate::commands::ignite::history::utils::record_change;
 use crate::commands::ignite::utils::{rollout, scale, update_deployment, update_deployment_config};
 use crate::state::State;
+use crate::store::history::Change;
 
 #[derive(Debug, Parser)]
 #[clap(about = "Update a deployment")]
@@ -65,6 +67,15 @@ pub async fn handle(options: Options, state: State) -> Result<()> {
         .await
         .map_err(|e| anyhow!("Failed to update deployment: {}", e))?;
 
+    record_change(
+        &state,
+        &deployment.id,
+        Change::Config {
+            before: Box::new(old_deployment.config.clone()),
+        },
+    )
+    .await;
+
     if deployment.can_scale() {
         if let Some(count) = container_options.containers {
             log::info!(
@@ -75,13 +86,33 @@ pub async fn handle(options: Options, state: State) -> Result<()> {
 
             scale(&state.http, &deployment.id, count).await?;
 
+            record_change(
+                &state,
+                &deployment.id,
+                Change::Scale {
+                    from: old_deployment.container_count,
+                    to: count,
+                },
+            )
+            .await;
+
             deployment.container_count = count;
         }
     }
 
     if deployment.can_rollout() && deployment.container_count > 0 && !options.no_rollout {
         log::info!("Rolling out new containers");
-        rollout(&state.http, &deployment.id).await?;
+        let rollout = rollout(&state.http, &deployment.id).await?;
+
+        record_change(
+            &state,
+            &deployment.id,
+            Change::Rollout {
+                rollout_id: Some(rollout.id),
+                canary: None,
+            },
+        )
+        .await;
     }
 
     log::info!(
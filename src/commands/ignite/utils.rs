@@ -13,6 +13,7 @@ use super::types::{
     CreateDeployment, Deployment, MultipleDeployments, Premade, Premades, RolloutEvent,
     ScaleRequest, SingleDeployment, Storage, Tier, Tiers,
 };
+use crate::commands::auth::docker::HOP_REGISTRY_URL;
 use crate::commands::containers::types::{ContainerOptions, ContainerType};
 use crate::commands::ignite::create::Options;
 use crate::commands::ignite::types::{
@@ -22,7 +23,7 @@ use crate::commands::projects::types::{Project, Sku};
 use crate::commands::projects::utils::{get_quotas, get_skus};
 use crate::state::http::HttpClient;
 use crate::utils::size::{parse_size, unit_multiplier};
-use crate::utils::{ask_question_iter, parse_key_val};
+use crate::utils::{ask_question_iter, dotenv};
 
 pub const WEB_IGNITE_URL: &str = "https://console.hop.io/ignite";
 
@@ -773,39 +774,54 @@ pub fn get_shell_array(entrypoint: &str) -> Vec<String> {
         .collect()
 }
 
-pub async fn env_file_to_map(path: PathBuf) -> Result<HashMap<String, String>> {
-    let mut env = HashMap::new();
+/// Points images that live in the Hop registry of one project to the registry of another
+pub fn rewrite_registry_image(image: &str, from_namespace: &str, to_namespace: &str) -> String {
+    let prefix = format!("{HOP_REGISTRY_URL}/{from_namespace}/");
+
+    match image.strip_prefix(&prefix) {
+        Some(rest) => format!("{HOP_REGISTRY_URL}/{to_namespace}/{rest}"),
+        None => image.to_string(),
+    }
+}
 
+/// Reads env variables from a `.env`, json or yaml file, picked by the extension
+pub async fn env_file_to_map(path: PathBuf) -> Result<HashMap<String, String>> {
     ensure!(
         path.exists(),
-        "Could not find .env file at {}",
+        "Could not find env file at {}",
         path.display()
     );
 
-    let file = fs::read_to_string(path).await?;
-    let lines = file.lines();
-
-    for line in lines {
-        let line = line.trim();
+    let file = fs::read_to_string(&path).await?;
 
-        // ignore comments
-        if line.starts_with('#') {
-            continue;
-        }
+    match path.extension().and_then(|ext| ext.to_str()) {
+        // yaml is a superset of json so both are parsed the same
+        Some("json" | "yaml" | "yml") => structured_env_to_map(&file)
+            .with_context(|| format!("Failed to parse env file {}", path.display())),
 
-        if line.is_empty() {
-            continue;
-        }
-
-        match parse_key_val(line) {
-            Ok((key, value)) => {
-                env.insert(key, value);
-            }
-            Err(e) => log::warn!("Failed to parse env file line: {}", e),
-        }
+        _ => dotenv::parse(&file)
+            .with_context(|| format!("Failed to parse env file {}", path.display())),
     }
+}
 
-    Ok(env)
+/// Env variables from a map of names to strings, numbers or booleans
+fn structured_env_to_map(file: &str) -> Result<HashMap<String, String>> {
+    let values = serde_yaml::from_str::<HashMap<String, serde_yaml::Value>>(file)
+        .context("Expected a map of names to values")?;
+
+    values
+        .into_iter()
+        .map(|(key, value)| {
+            let value = match value {
+                serde_yaml::Value::String(value) => value,
+                serde_yaml::Value::Number(value) => value.to_string(),
+                serde_yaml::Value::Bool(value) => value.to_string(),
+                _ => bail!("Value of `{key}` has to be a string, number or boolean"),
+            };
+
+            Ok((key, value))
+        })
+        .collect()
 }
 
 pub fn format_premade(premades: &[Premade], title: bool) -> Result<Vec<String>> {
@@ -903,6 +919,39 @@ mod test {
         assert_eq!(entrypoint_array.next(), None);
     }
 
+    #[test]
+    fn test_structured_env_to_map() {
+        let env =
+            structured_env_to_map(r#"{"PORT": 8080, "DEBUG": false, "NAME": "api"}"#).unwrap();
+
+        assert_eq!(env.get("PORT").map(String::as_str), Some("8080"));
+        assert_eq!(env.get("DEBUG").map(String::as_str), Some("false"));
+        assert_eq!(env.get("NAME").map(String::as_str), Some("api"));
+
+        let env = structured_env_to_map("PORT: 8080\nURL: ${secrets.URL}\n").unwrap();
+
+        assert_eq!(env.get("URL").map(String::as_str), Some("${secrets.URL}"));
+
+        assert!(structured_env_to_map("LIST: [1, 2]").is_err());
+        assert!(structured_env_to_map("- not a map").is_err());
+    }
+
+    #[test]
+    fn test_rewrite_registry_image() {
+        assert_eq!(
+            rewrite_registry_image("registry.hop.io/staging/api:latest", "staging", "prod"),
+            "registry.hop.io/prod/api:latest"
+        );
+        assert_eq!(
+            rewrite_registry_image("registry.hop.io/other/api", "staging", "prod"),
+            "registry.hop.io/other/api"
+        );
+        assert_eq!(
+            rewrite_registry_image("nginx:latest", "staging", "prod"),
+            "nginx:latest"
+        );
+    }
+
     #[test]
     fn test_price_estimate() {
         let skus = vec![
@@ -9,7 +9,8 @@ use crate::commands::ignite::utils::get_deployment;
 use crate::commands::projects::utils::format_project;
 use crate::config::EXEC_NAME;
 use crate::state::State;
-use crate::store::hopfile::HopFile;
+use crate::store::hopfile::{HopFile, HopFileConfig, HopFileEnvironment};
+use crate::store::Store;
 
 #[derive(Debug, Parser)]
 #[clap(about = "Link an existing deployment to a hopfile")]
@@ -23,9 +24,21 @@ pub struct Options {
 
     #[clap(help = "ID of the deployment")]
     deployment: Option<String>,
+
+    #[clap(
+        long,
+        help = "Link the deployment as a named environment of the hopfile"
+    )]
+    environment: Option<String>,
+
+    #[clap(long, help = "Require confirmation when deploying to the environment")]
+    production: bool,
+
+    #[clap(long, help = "Deploy to the environment when none is specified")]
+    default: bool,
 }
 
-pub async fn handle(options: Options, state: State) -> Result<()> {
+pub async fn handle(options: Options, mut state: State) -> Result<()> {
     let mut dir = current_dir()?;
 
     if let Some(path) = options.path {
@@ -34,7 +47,14 @@ pub async fn handle(options: Options, state: State) -> Result<()> {
 
     ensure!(dir.is_dir(), "{dir:?} is not a directory");
 
-    if HopFile::find(dir.clone()).await.is_some() {
+    ensure!(
+        options.environment.is_some() || !(options.production || options.default),
+        "`--production` and `--default` can only be used with `--environment`"
+    );
+
+    let existing = HopFile::find(dir.clone()).await;
+
+    if existing.is_some() && options.environment.is_none() {
         log::warn!("A hopfile was found {dir:?}, did you mean to `{EXEC_NAME} deploy`?");
     }
 
@@ -67,15 +87,55 @@ pub async fn handle(options: Options, state: State) -> Result<()> {
         }
     };
 
-    HopFile::new(dir.join("hop.yml"), &project.id, &deployment.id)
-        .save()
-        .await?;
+    let Some(environment) = options.environment else {
+        HopFile::new(dir.join("hop.yml"), &project.id, &deployment.id)
+            .save()
+            .await?;
+
+        log::info!(
+            "Deployment `{}` ({}) linked",
+            deployment.name,
+            deployment.id
+        );
+
+        return Ok(());
+    };
+
+    let mut hopfile =
+        existing.unwrap_or_else(|| HopFile::new(dir.join("hop.yml"), &project.id, &deployment.id));
+
+    let env = hopfile
+        .environments
+        .remove(&environment)
+        .map(|existing| existing.env)
+        .unwrap_or_default();
+
+    hopfile.environments.insert(
+        environment.clone(),
+        HopFileEnvironment {
+            config: HopFileConfig {
+                project_id: project.id.clone(),
+                deployment_id: deployment.id.clone(),
+            },
+            env,
+            production: options.production,
+        },
+    );
+
+    hopfile.save().await?;
 
     log::info!(
-        "Deployment `{}` ({}) linked",
+        "Deployment `{}` ({}) linked as environment `{environment}`",
         deployment.name,
         deployment.id
     );
 
+    if options.default {
+        state.ctx.default_environment = Some(environment.clone());
+        state.ctx.save().await?;
+
+        log::info!("Environment `{environment}` is now the default");
+    }
+
     Ok(())
 }
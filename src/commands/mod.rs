@@ -1,4 +1,5 @@
 pub mod auth;
+mod build;
 mod channels;
 mod completions;
 pub mod containers;
@@ -9,6 +10,7 @@ pub mod ignite;
 mod link;
 mod oops;
 mod payment;
+mod preview;
 pub mod projects;
 mod secrets;
 mod tunnel;
@@ -32,6 +34,7 @@ pub enum Commands {
     #[clap(alias = "secret")]
     Secrets(secrets::Options),
     Deploy(deploy::Options),
+    Build(build::Options),
     #[clap(alias = "info", alias = "ctx")]
     Whoami(whoami::Options),
     Ignite(ignite::Options),
@@ -56,6 +59,7 @@ pub enum Commands {
         alias = "billing"
     )]
     Payment(payment::Options),
+    Preview(preview::Options),
     #[clap(alias = "fwd", alias = "forward")]
     Tunnel(tunnel::Options),
     #[clap(alias = "volume", alias = "v")]
@@ -76,13 +80,15 @@ pub async fn handle_command(command: Commands, mut state: State) -> Result<()> {
             completions::handle(options, state);
             Ok(())
         }
+        // logs in by itself when a deployment is needed
+        Commands::Build(options) => build::handle(options, state).await,
 
         authorized_command => {
             // login so these commands can run
             state.login(None).await?;
 
             match authorized_command {
-                Commands::Auth(_) | Commands::Completions(_) => {
+                Commands::Auth(_) | Commands::Completions(_) | Commands::Build(_) => {
                     unreachable!()
                 }
 
@@ -103,6 +109,7 @@ pub async fn handle_command(command: Commands, mut state: State) -> Result<()> {
                 Commands::Tunnel(options) => tunnel::handle(&options, state).await,
                 Commands::FromCompose(options) => from_compose::handle(options, state).await,
                 Commands::Payment(options) => payment::handle(options, state).await,
+                Commands::Preview(options) => preview::handle(options, state).await,
                 Commands::Volumes(options) => volumes::handle(options, state).await,
                 Commands::Backup(options) => backup::handle(options, state).await,
                 Commands::Webhooks(options) => webhooks::handle(options, state).await,
@@ -1,13 +1,21 @@
-use anyhow::{anyhow, Result};
+use anyhow::{bail, ensure, Context, Result};
 use clap::Parser;
+use console::Term;
 
-use super::ignite::builds::types::BuildState;
-use super::ignite::builds::utils::get_all_builds;
-use super::ignite::utils::promote;
+use super::deploy::{connect_to_leap, wait_for_deployment_rollout};
+use super::ignite::builds::types::{Build, BuildState};
+use super::ignite::builds::utils::{format_builds, get_all_builds};
+use super::ignite::utils::{get_deployment, promote};
 use crate::commands::ignite::groups::utils::fetch_grouped_deployments;
+use crate::commands::ignite::history::utils::record_change;
 use crate::commands::projects::utils::format_project;
 use crate::state::State;
+use crate::store::history::Change;
 use crate::store::hopfile::HopFile;
+use crate::utils::relative_time;
+
+// successful builds offered in the picker
+const PICKER_BUILDS: usize = 10;
 
 #[derive(Debug, Parser)]
 #[clap(about = "Instantly roll back your deployment to a previous build")]
@@ -15,13 +23,42 @@ use crate::store::hopfile::HopFile;
 pub struct Options {
     #[clap(help = "ID of the deployment")]
     pub deployment: Option<String>,
+
+    #[clap(
+        long,
+        help = "ID of the build to roll back to",
+        conflicts_with = "steps"
+    )]
+    pub build: Option<String>,
+
+    #[clap(
+        long,
+        help = "Roll back this many successful builds, 1 is the one before the latest"
+    )]
+    pub steps: Option<usize>,
+
+    #[clap(short, long, help = "Skip the confirmation")]
+    pub yes: bool,
+
+    #[clap(short, long, help = "Wait for the rollout of the build to finish")]
+    pub wait: bool,
 }
 
 pub async fn handle(options: &Options, state: State) -> Result<()> {
-    let deployment_id = if let Some(ref id) = options.deployment {
-        id.clone()
+    let (deployment_id, project) = if let Some(ref id) = options.deployment {
+        (id.clone(), state.ctx.current_project_error()?)
     } else if let Some(hopfile) = HopFile::find_current().await {
-        hopfile.config.deployment_id
+        let project = state
+            .ctx
+            .find_project_by_id_or_namespace(&hopfile.config.project_id)
+            .with_context(|| {
+                format!(
+                    "Could not find project with id {}",
+                    hopfile.config.project_id
+                )
+            })?;
+
+        (hopfile.config.deployment_id, project)
     } else {
         let project = state.ctx.current_project_error()?;
 
@@ -44,22 +81,125 @@ pub async fn handle(options: &Options, state: State) -> Result<()> {
             console::Term::stderr().clear_last_lines(1)?
         };
 
-        deployments[idx].id.clone()
+        (deployments[idx].id.clone(), project)
     };
 
-    let build_id = if let Some(build) = get_all_builds(&state.http, &deployment_id)
+    let deployment = get_deployment(&state.http, &deployment_id).await?;
+
+    let builds = get_all_builds(&state.http, &deployment.id)
         .await?
         .into_iter()
-        .find(|b| matches!(b.state, BuildState::Succeeded))
+        .filter(|b| matches!(b.state, BuildState::Succeeded))
+        .collect::<Vec<_>>();
+
+    ensure!(!builds.is_empty(), "No successful builds found");
+
+    // pipelines can't answer prompts
+    let interactive = !state.is_ci && Term::stderr().is_term();
+
+    let build = select_build(options, interactive, builds)?;
+
+    if !options.yes
+        && interactive
+        && !dialoguer::Confirm::new()
+            .with_prompt(format!(
+                "Roll back `{}` to build `{}` from {}?",
+                deployment.name,
+                build.id,
+                relative_time(build.started_at)
+            ))
+            .default(false)
+            .interact_opt()?
+            .unwrap_or(false)
     {
-        build.id
+        bail!("Aborted by user");
+    }
+
+    // connect before promoting so the rollout can not be missed
+    let mut leap = if options.wait {
+        Some(connect_to_leap(&state, &project.id).await?)
     } else {
-        return Err(anyhow!("No successful builds found."));
+        None
     };
 
-    promote(&state.http, &deployment_id, &build_id).await?;
+    promote(&state.http, &deployment.id, &build.id).await?;
+
+    record_change(
+        &state,
+        &deployment.id,
+        Change::Promote {
+            build_id: build.id.clone(),
+        },
+    )
+    .await;
+
+    log::info!(
+        "Deployment `{}` rolled back to build `{}`",
+        deployment.name,
+        build.id
+    );
 
-    log::info!("Deployment `{deployment_id}` rolled back to build `{build_id}`");
+    if let Some(leap) = leap.as_mut() {
+        let result = wait_for_deployment_rollout(leap, &project.id, &deployment.id).await;
+
+        leap.close().await;
+
+        result?;
+    }
 
     Ok(())
 }
+
+/// Build from the flags or the picker, the builds are sorted from the newest
+fn select_build(options: &Options, interactive: bool, mut builds: Vec<Build>) -> Result<Build> {
+    if let Some(id) = &options.build {
+        let idx = builds
+            .iter()
+            .position(|b| &b.id == id)
+            .with_context(|| format!("`{id}` is not a successful build of the deployment"))?;
+
+        return Ok(builds.swap_remove(idx));
+    }
+
+    if let Some(steps) = options.steps {
+        ensure!(
+            steps > 0,
+            "`--steps` has to be at least 1, 0 is the latest build"
+        );
+
+        ensure!(
+            steps < builds.len(),
+            "Can not roll back {steps} builds, there are only {} successful builds",
+            builds.len()
+        );
+
+        return Ok(builds.swap_remove(steps));
+    }
+
+    // the latest successful build, same as before the picker existed
+    if !interactive {
+        return Ok(builds.swap_remove(0));
+    }
+
+    builds.truncate(PICKER_BUILDS);
+
+    let builds_fmt = format_builds(&builds, false)
+        .into_iter()
+        .enumerate()
+        .map(|(idx, build)| {
+            if idx == 0 {
+                format!("{build} (latest)")
+            } else {
+                build
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let idx = dialoguer::Select::new()
+        .with_prompt("Select a build to roll back to")
+        .items(&builds_fmt)
+        .default(1.min(builds.len() - 1))
+        .interact()?;
+
+    Ok(builds.swap_remove(idx))
+}
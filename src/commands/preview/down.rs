@@ -0,0 +1,61 @@
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+
+use super::utils::{get_current_branch, get_preview_branch, get_preview_context};
+use crate::commands::ignite::utils::{delete_deployment, get_all_deployments};
+use crate::state::State;
+
+#[derive(Debug, Parser)]
+#[clap(about = "Delete the preview deployment of a branch")]
+#[group(skip)]
+pub struct Options {
+    #[clap(
+        short,
+        long,
+        help = "Branch to delete the preview of, defaults to the current one"
+    )]
+    pub branch: Option<String>,
+
+    #[clap(short, long, help = "Skip confirmation")]
+    pub yes: bool,
+
+    #[clap(
+        long,
+        help = "Environment from the hopfile to take the deployment from, defaults to the one set with `hop link --default`"
+    )]
+    pub environment: Option<String>,
+}
+
+pub async fn handle(options: Options, state: State) -> Result<()> {
+    let context = get_preview_context(&state, options.environment.as_deref()).await?;
+
+    let branch = match options.branch {
+        Some(branch) => branch,
+        None => get_current_branch(&context.dir).await?,
+    };
+
+    let deployment = get_all_deployments(&state.http, &context.project.id)
+        .await?
+        .into_iter()
+        .find(|deployment| get_preview_branch(deployment, &context.deployment.id) == Some(&branch))
+        .with_context(|| format!("Could not find a preview for branch `{branch}`"))?;
+
+    if !options.yes
+        && !dialoguer::Confirm::new()
+            .with_prompt(format!(
+                "Are you sure you want to delete preview `{}`?",
+                deployment.name
+            ))
+            .default(false)
+            .interact_opt()?
+            .unwrap_or(false)
+    {
+        bail!("Aborted by user");
+    }
+
+    delete_deployment(&state.http, &deployment.id).await?;
+
+    log::info!("Preview `{}` deleted", deployment.name);
+
+    Ok(())
+}
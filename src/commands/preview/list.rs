@@ -0,0 +1,61 @@
+use anyhow::Result;
+use clap::Parser;
+
+use super::utils::{format_previews, get_preview_branch, get_preview_context};
+use crate::commands::gateways::util::get_all_gateways;
+use crate::commands::ignite::utils::get_all_deployments;
+use crate::state::State;
+
+#[derive(Debug, Parser)]
+#[clap(about = "List the preview deployments of the current deployment")]
+#[group(skip)]
+pub struct Options {
+    #[clap(short, long, help = "Only print the IDs of the previews")]
+    pub quiet: bool,
+
+    #[clap(
+        long,
+        help = "Environment from the hopfile to take the deployment from, defaults to the one set with `hop link --default`"
+    )]
+    pub environment: Option<String>,
+}
+
+pub async fn handle(options: Options, state: State) -> Result<()> {
+    let context = get_preview_context(&state, options.environment.as_deref()).await?;
+
+    let mut previews = vec![];
+
+    for deployment in get_all_deployments(&state.http, &context.project.id).await? {
+        let Some(branch) = get_preview_branch(&deployment, &context.deployment.id).cloned() else {
+            continue;
+        };
+
+        previews.push((deployment, branch));
+    }
+
+    if options.quiet {
+        let ids = previews
+            .iter()
+            .map(|(deployment, _)| deployment.id.as_str())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        println!("{ids}");
+
+        return Ok(());
+    }
+
+    let mut with_gateways = vec![];
+
+    for (deployment, branch) in previews {
+        let gateways = get_all_gateways(&state.http, &deployment.id).await?;
+
+        with_gateways.push((deployment, branch, gateways));
+    }
+
+    let previews_fmt = format_previews(&with_gateways, true);
+
+    println!("{}", previews_fmt.join("\n"));
+
+    Ok(())
+}
@@ -0,0 +1,34 @@
+mod down;
+mod list;
+mod up;
+mod utils;
+
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+
+use crate::state::State;
+
+#[derive(Debug, Subcommand)]
+pub enum Commands {
+    Up(up::Options),
+    #[clap(alias = "rm")]
+    Down(down::Options),
+    #[clap(name = "ls", alias = "list")]
+    List(list::Options),
+}
+
+#[derive(Debug, Parser)]
+#[clap(about = "Manage preview deployments for git branches")]
+#[group(skip)]
+pub struct Options {
+    #[clap(subcommand)]
+    pub commands: Commands,
+}
+
+pub async fn handle(options: Options, state: State) -> Result<()> {
+    match options.commands {
+        Commands::Up(options) => up::handle(options, state).await,
+        Commands::Down(options) => down::handle(options, state).await,
+        Commands::List(options) => list::handle(options, state).await,
+    }
+}
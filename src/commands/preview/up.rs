@@ -0,0 +1,221 @@
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+
+use super::utils::{
+    get_current_branch, get_preview_branch, get_preview_context, preview_name, PREVIEW_BRANCH_ENV,
+    PREVIEW_OF_ENV,
+};
+use crate::commands::auth::docker::HOP_REGISTRY_URL;
+use crate::commands::containers::utils::create_containers;
+use crate::commands::deploy::{builder, connect_to_leap};
+use crate::commands::gateways::types::{GatewayConfig, GatewayProtocol, GatewayType};
+use crate::commands::gateways::util::get_all_gateways;
+use crate::commands::ignite::clone::utils::{create_clone, get_clone_parts};
+use crate::commands::ignite::history::utils::record_change;
+use crate::commands::ignite::types::{CreateDeployment, Env, Image};
+use crate::commands::ignite::utils::{get_all_deployments, rollout, update_deployment};
+use crate::commands::projects::utils::format_project;
+use crate::state::State;
+use crate::store::history::Change;
+use crate::utils::urlify;
+
+#[derive(Debug, Parser)]
+#[clap(about = "Build the working tree into a preview deployment for the current branch")]
+#[group(skip)]
+pub struct Options {
+    #[clap(
+        short,
+        long,
+        help = "Branch to create the preview for, defaults to the current one"
+    )]
+    pub branch: Option<String>,
+
+    #[clap(
+        short = 'e',
+        long = "env",
+        help = "Environment variables to override in the preview, in the form of KEY=VALUE"
+    )]
+    pub env: Vec<Env>,
+
+    #[clap(
+        long,
+        help = "Port to expose, defaults to the external gateways of the deployment"
+    )]
+    pub port: Option<u16>,
+
+    #[clap(
+        long,
+        help = "Environment from the hopfile to take the deployment from, defaults to the one set with `hop link --default`"
+    )]
+    pub environment: Option<String>,
+
+    #[clap(
+        long,
+        help = "Upload files that look like secrets, like .env or private keys, without asking"
+    )]
+    pub allow_secrets: bool,
+}
+
+pub async fn handle(options: Options, state: State) -> Result<()> {
+    let context = get_preview_context(&state, options.environment.as_deref()).await?;
+
+    // before anything is created for the preview
+    builder::check_secrets(&context.dir, options.allow_secrets).await?;
+
+    let branch = match options.branch {
+        Some(branch) => branch,
+        None => get_current_branch(&context.dir).await?,
+    };
+
+    let name = preview_name(&context.deployment.name, &branch);
+
+    log::info!(
+        "Preview `{name}` of `{}` for branch `{branch}` in project {}",
+        context.deployment.name,
+        format_project(&context.project)
+    );
+
+    let deployments = get_all_deployments(&state.http, &context.project.id).await?;
+
+    // previews are found by their branch, their name may be from an older naming scheme
+    let existing = deployments
+        .iter()
+        .find(|deployment| get_preview_branch(deployment, &context.deployment.id) == Some(&branch))
+        .cloned();
+
+    if existing.is_none() && deployments.iter().any(|deployment| deployment.name == name) {
+        bail!("Deployment `{name}` already exists and is not a preview of `{branch}`");
+    }
+
+    let deployment = match existing {
+        Some(deployment) if options.env.is_empty() => deployment,
+
+        // overrides are applied again so they also reach existing previews
+        Some(deployment) => {
+            let mut update = CreateDeployment::from(deployment.clone());
+            update.name = None;
+            update.type_ = None;
+
+            for Env(key, value) in options.env {
+                update.env.insert(key, value);
+            }
+
+            if update.env == deployment.config.env {
+                deployment
+            } else {
+                let updated = update_deployment(&state.http, &deployment.id, &update).await?;
+
+                record_change(
+                    &state,
+                    &deployment.id,
+                    Change::Config {
+                        before: Box::new(deployment.config),
+                    },
+                )
+                .await;
+
+                log::info!("Updated the env of `{}`", updated.name);
+
+                updated
+            }
+        }
+
+        None => {
+            let mut parts = get_clone_parts(&state.http, &context.deployment, &name).await?;
+
+            let has_external = |port: Option<u16>| {
+                parts.gateways.iter().any(|gateway| {
+                    gateway.type_ == Some(GatewayType::External)
+                        && (port.is_none() || gateway.target_port == port)
+                })
+            };
+
+            // a preview without an external gateway could not be reached
+            let target_port = match options.port {
+                Some(port) => Some(port).filter(|port| !has_external(Some(*port))),
+                None if has_external(None) => None,
+                None => Some(
+                    parts
+                        .gateways
+                        .first()
+                        .and_then(|gateway| gateway.target_port)
+                        .context("Could not find a port to expose, use --port to pick one")?,
+                ),
+            };
+
+            if let Some(target_port) = target_port {
+                parts.gateways.push(GatewayConfig {
+                    type_: Some(GatewayType::External),
+                    protocol: Some(GatewayProtocol::Http),
+                    name: Some("preview".to_string()),
+                    target_port: Some(target_port),
+                    internal_domain: None,
+                });
+            }
+
+            let mut config = CreateDeployment::from(context.deployment.clone());
+
+            config.name = Some(name.clone());
+            config.image = Some(Image {
+                name: format!("{HOP_REGISTRY_URL}/{}/{name}", context.project.namespace),
+            });
+
+            config
+                .env
+                .insert(PREVIEW_OF_ENV.to_string(), context.deployment.id.clone());
+            config
+                .env
+                .insert(PREVIEW_BRANCH_ENV.to_string(), branch.clone());
+
+            for Env(key, value) in options.env {
+                config.env.insert(key, value);
+            }
+
+            create_clone(&state.http, &context.project.id, &config, &parts).await?
+        }
+    };
+
+    let mut leap = connect_to_leap(&state, &context.project.id).await?;
+
+    builder::build(
+        &state,
+        &context.project.id,
+        &deployment.id,
+        context.dir.clone(),
+        None,
+        &mut leap,
+    )
+    .await?;
+
+    leap.close().await;
+
+    if deployment.can_rollout() {
+        let rollout = rollout(&state.http, &deployment.id).await?;
+
+        log::info!("Rolling out new containers");
+
+        record_change(
+            &state,
+            &deployment.id,
+            Change::Rollout {
+                rollout_id: Some(rollout.id),
+                canary: None,
+            },
+        )
+        .await;
+    } else if deployment.container_count == 0 {
+        create_containers(&state.http, &deployment.id, 1).await?;
+    }
+
+    let gateways = get_all_gateways(&state.http, &deployment.id).await?;
+
+    match gateways.iter().find(|gateway| !gateway.is_internal()) {
+        Some(gateway) => log::info!("Preview is available at {}", urlify(&gateway.full_url())),
+        None => log::warn!(
+            "Preview `{}` does not have an external gateway",
+            deployment.name
+        ),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,194 @@
+use std::env::current_dir;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{ensure, Context, Result};
+use chrono::{DateTime, Utc};
+use sha1::{Digest, Sha1};
+use tabwriter::TabWriter;
+use tokio::process::Command;
+
+use crate::commands::gateways::types::Gateway;
+use crate::commands::ignite::types::Deployment;
+use crate::commands::ignite::utils::get_deployment;
+use crate::commands::projects::types::Project;
+use crate::state::State;
+use crate::store::hopfile::HopFile;
+use crate::utils::relative_time;
+
+/// Env variable holding the branch a preview was created for
+pub const PREVIEW_BRANCH_ENV: &str = "HOP_PREVIEW_BRANCH";
+/// Env variable holding the ID of the deployment a preview was cloned from
+pub const PREVIEW_OF_ENV: &str = "HOP_PREVIEW_OF";
+
+const MAX_NAME_LENGTH: usize = 20;
+const BRANCH_HASH_LENGTH: usize = 6;
+
+pub struct PreviewContext {
+    pub dir: PathBuf,
+    pub project: Project,
+    pub deployment: Deployment,
+}
+
+/// Resolves the project and deployment of the environment from the hopfile in the current directory
+pub async fn get_preview_context(
+    state: &State,
+    environment: Option<&str>,
+) -> Result<PreviewContext> {
+    let dir = current_dir().context("Could not get current directory")?;
+
+    let hopfile = HopFile::find(dir)
+        .await
+        .context("No hopfile found, run `hop deploy` first")?;
+
+    let dir = hopfile
+        .path
+        .parent()
+        .context("Could not get the parent dir from the hop file location")?
+        .to_path_buf();
+
+    let config = hopfile
+        .environment(environment, state.ctx.default_environment.as_deref())?
+        .map_or(&hopfile.config, |(_, environment)| &environment.config);
+
+    let project = state
+        .ctx
+        .find_project_by_id_or_namespace(&config.project_id)
+        .with_context(|| format!("Could not find project with id {}", config.project_id))?;
+
+    let deployment = get_deployment(&state.http, &config.deployment_id).await?;
+
+    Ok(PreviewContext {
+        dir,
+        project,
+        deployment,
+    })
+}
+
+pub async fn get_current_branch(dir: &Path) -> Result<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--abbrev-ref", "HEAD"])
+        .current_dir(dir)
+        .output()
+        .await
+        .context("Could not run git, is it installed?")?;
+
+    ensure!(
+        output.status.success(),
+        "Could not get the current git branch: {}",
+        String::from_utf8_lossy(&output.stderr).trim()
+    );
+
+    let branch = String::from_utf8(output.stdout)?.trim().to_string();
+
+    ensure!(
+        branch != "HEAD",
+        "Not on a branch, use --branch to pick a name for the preview"
+    );
+
+    Ok(branch)
+}
+
+/// Deployment names are limited to 20 lowercase alphanumeric characters or hyphens,
+/// a hash of the full branch keeps branches that only differ after the cut apart
+pub fn preview_name(deployment: &str, branch: &str) -> String {
+    let mut slug = String::new();
+
+    for c in branch.to_lowercase().chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c);
+        } else if !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    let hash = format!("{:x}", Sha1::digest(branch.as_bytes()));
+
+    let name = format!("{deployment}-{}", slug.trim_matches('-'))
+        .chars()
+        .take(MAX_NAME_LENGTH - BRANCH_HASH_LENGTH - 1)
+        .collect::<String>();
+
+    format!(
+        "{}-{}",
+        name.trim_end_matches('-'),
+        &hash[..BRANCH_HASH_LENGTH]
+    )
+}
+
+/// Returns the branch of the preview if the deployment is a preview of `of`
+pub fn get_preview_branch<'a>(deployment: &'a Deployment, of: &str) -> Option<&'a String> {
+    if deployment
+        .config
+        .env
+        .get(PREVIEW_OF_ENV)
+        .map(String::as_str)
+        != Some(of)
+    {
+        return None;
+    }
+
+    deployment.config.env.get(PREVIEW_BRANCH_ENV)
+}
+
+pub fn format_previews(
+    previews: &[(Deployment, String, Vec<Gateway>)],
+    title: bool,
+) -> Vec<String> {
+    let mut tw = TabWriter::new(vec![]);
+
+    if title {
+        writeln!(tw, "NAME\tBRANCH\tURL\tAGE").unwrap();
+    }
+
+    for (deployment, branch, gateways) in previews {
+        let url = gateways
+            .iter()
+            .find(|gateway| !gateway.is_internal())
+            .map(|gateway| gateway.full_url())
+            .unwrap_or_else(|| "-".to_string());
+
+        let age = deployment
+            .created_at
+            .parse::<DateTime<Utc>>()
+            .map(relative_time)
+            .unwrap_or_else(|_| "-".to_string());
+
+        writeln!(tw, "{}\t{branch}\t{url}\t{age}", deployment.name).unwrap();
+    }
+
+    String::from_utf8(tw.into_inner().unwrap())
+        .unwrap()
+        .lines()
+        .map(std::string::ToString::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_preview_name() {
+        assert_eq!(preview_name("api", "feature/login"), "api-feature-l-ccb6f3");
+        assert_eq!(preview_name("api", "Fix__Bug#12"), "api-fix-bug-1-4df6eb");
+        assert_eq!(
+            preview_name("backend", "feature/very-long-branch-name"),
+            "backend-featu-0786db"
+        );
+        assert_eq!(
+            preview_name("web", "a-very-long-name-x"),
+            "web-a-very-lo-ccd253"
+        );
+    }
+
+    #[test]
+    fn test_preview_name_same_prefix() {
+        let a = preview_name("api", "feature/login-a");
+        let b = preview_name("api", "feature/login-b");
+
+        assert_eq!(a, "api-feature-l-407bee");
+        assert_eq!(b, "api-feature-l-cde8e4");
+        assert!(a.len() <= MAX_NAME_LENGTH && b.len() <= MAX_NAME_LENGTH);
+    }
+}
@@ -3,6 +3,8 @@ mod delete;
 pub mod finance;
 pub mod info;
 mod list;
+mod restore;
+pub mod snapshot;
 mod switch;
 pub mod types;
 pub mod utils;
@@ -21,6 +23,8 @@ pub enum Commands {
     List(list::Options),
     #[clap(name = "rm", alias = "delete")]
     Delete(delete::Options),
+    Snapshot(snapshot::Options),
+    Restore(restore::Options),
 }
 
 #[derive(Debug, Parser)]
@@ -38,5 +42,7 @@ pub async fn handle(options: Options, state: State) -> anyhow::Result<()> {
         Commands::Create(options) => create::handle(options, state).await,
         Commands::List(options) => list::handle(options, state),
         Commands::Info(options) => info::handle(&options, state),
+        Commands::Snapshot(options) => snapshot::handle(options, state).await,
+        Commands::Restore(options) => restore::handle(options, state).await,
     }
 }
@@ -0,0 +1,276 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use clap::Parser;
+use tokio::fs;
+
+use super::snapshot::types::{Snapshot, SNAPSHOT_VERSION};
+use crate::commands::auth::docker::HOP_REGISTRY_URL;
+use crate::commands::channels::utils::create_channel;
+use crate::commands::containers::utils::create_containers;
+use crate::commands::domains::util::attach_domain;
+use crate::commands::gateways::util::create_gateway;
+use crate::commands::ignite::health::utils::create_health_check;
+use crate::commands::ignite::types::{CreateDeployment, Deployment};
+use crate::commands::ignite::utils::{
+    create_deployment, get_all_deployments, rewrite_registry_image,
+};
+use crate::commands::projects::utils::format_project;
+use crate::commands::secrets::types::Secrets;
+use crate::state::State;
+
+#[derive(Debug, Parser)]
+#[clap(
+    about = "Recreate a project snapshot in the current project",
+    long_about = "Recreate a project snapshot in the current project, pick another one with `--project`"
+)]
+#[group(skip)]
+pub struct Options {
+    #[clap(help = "Path to the snapshot file")]
+    pub snapshot: PathBuf,
+
+    #[clap(short, long, help = "Skip confirmation")]
+    pub yes: bool,
+}
+
+pub async fn handle(options: Options, state: State) -> Result<()> {
+    let project = state.ctx.current_project_error()?;
+
+    let snapshot = fs::read_to_string(&options.snapshot)
+        .await
+        .with_context(|| format!("Could not read {}", options.snapshot.display()))?;
+
+    let snapshot: Snapshot =
+        serde_json::from_str(&snapshot).context("Could not parse the snapshot")?;
+
+    ensure!(
+        snapshot.version <= SNAPSHOT_VERSION,
+        "Snapshot version {} is not supported, please update the CLI",
+        snapshot.version
+    );
+
+    let (existing, existing_groups, existing_webhooks) = tokio::join!(
+        get_all_deployments(&state.http, &project.id),
+        state.hop.ignite.groups.get_all(&project.id),
+        state.hop.webhooks.get_all(&project.id),
+    );
+
+    let existing = existing?;
+
+    let conflicts = snapshot
+        .deployments
+        .iter()
+        .filter(|deployment| existing.iter().any(|d| d.name == deployment.name))
+        .map(|deployment| deployment.name.as_str())
+        .collect::<Vec<_>>();
+
+    if !conflicts.is_empty() {
+        bail!(
+            "Deployments {} already exist in project `{}`",
+            conflicts.join(", "),
+            project.namespace
+        );
+    }
+
+    log::info!(
+        "Restoring snapshot of `{}` from {} into project {}",
+        snapshot.project.namespace,
+        snapshot.created_at,
+        format_project(&project)
+    );
+
+    if !options.yes
+        && !dialoguer::Confirm::new()
+            .with_prompt(format!(
+                "Create {} deployments, {} groups, {} webhooks and {} channels?",
+                snapshot.deployments.len(),
+                snapshot.groups.len(),
+                snapshot.webhooks.len(),
+                snapshot.channels.len()
+            ))
+            .default(false)
+            .interact_opt()?
+            .unwrap_or(false)
+    {
+        bail!("Aborted by user");
+    }
+
+    // groups and webhooks left behind by an earlier restore are reused
+    let mut groups = existing_groups?
+        .into_iter()
+        .map(|group| (group.name, group.id))
+        .collect::<HashMap<_, _>>();
+
+    let existing_webhooks = existing_webhooks?;
+
+    for group in &snapshot.groups {
+        if groups.contains_key(&group.name) {
+            log::info!("Group `{}` already exists", group.name);
+
+            continue;
+        }
+
+        let created = state
+            .hop
+            .ignite
+            .groups
+            .create(&project.id, &group.name, &[])
+            .await?;
+
+        log::info!("Group `{}` created", created.name);
+
+        groups.insert(group.name.clone(), created.id);
+    }
+
+    let mut needs_image = vec![];
+
+    for snapshot_deployment in &snapshot.deployments {
+        let mut config = CreateDeployment::from(Deployment {
+            name: snapshot_deployment.name.clone(),
+            config: snapshot_deployment.config.clone(),
+            ..Default::default()
+        });
+
+        // images in the Hop registry are not copied over, these deployments need a new build
+        let mut from_registry = false;
+
+        if let Some(image) = config.image.as_mut() {
+            let rewritten = rewrite_registry_image(
+                &image.name,
+                &snapshot.project.namespace,
+                &project.namespace,
+            );
+
+            from_registry = rewritten != image.name;
+            image.name = rewritten;
+        }
+
+        let deployment = create_deployment(&state.http, &project.id, &config).await?;
+
+        log::info!(
+            "Deployment `{}` ({}) created",
+            deployment.name,
+            deployment.id
+        );
+
+        for gateway in &snapshot_deployment.gateways {
+            let created = create_gateway(&state.http, &deployment.id, &gateway.config).await?;
+
+            for domain in &gateway.domains {
+                if let Err(error) = attach_domain(&state.http, &created.id, domain).await {
+                    log::warn!("Could not attach domain `{domain}`: {error}");
+                }
+            }
+        }
+
+        for health_check in &snapshot_deployment.health_checks {
+            create_health_check(&state.http, &deployment.id, health_check.clone()).await?;
+        }
+
+        if let Some(group) = &snapshot_deployment.group {
+            let group_id = groups
+                .get(group)
+                .ok_or_else(|| anyhow!("Group `{group}` is not part of the snapshot"))?;
+
+            state
+                .hop
+                .ignite
+                .groups
+                .move_deployment(Some(group_id), &deployment.id)
+                .await?;
+        }
+
+        if from_registry {
+            needs_image.push(snapshot_deployment.name.as_str());
+        } else if snapshot_deployment.target_container_count > 0 {
+            create_containers(
+                &state.http,
+                &deployment.id,
+                snapshot_deployment.target_container_count,
+            )
+            .await?;
+        }
+    }
+
+    let mut new_webhooks = vec![];
+
+    for webhook in &snapshot.webhooks {
+        if existing_webhooks
+            .iter()
+            .any(|existing| existing.webhook_url == webhook.url)
+        {
+            log::info!("Webhook for {} already exists", webhook.url);
+
+            continue;
+        }
+
+        let created = state
+            .hop
+            .webhooks
+            .create(&project.id, &webhook.url, &webhook.events)
+            .await?;
+
+        log::info!(
+            "Webhook for {} created with secret {}",
+            webhook.url,
+            created.secret.as_deref().unwrap_or("-")
+        );
+
+        new_webhooks.push(webhook.url.as_str());
+    }
+
+    for channel in &snapshot.channels {
+        if let Err(error) = create_channel(
+            &state.http,
+            &project.id,
+            &channel.type_,
+            &channel.state,
+            Some(&channel.id),
+        )
+        .await
+        {
+            log::warn!("Could not create channel `{}`: {error}", channel.id);
+        }
+    }
+
+    let secrets = state
+        .http
+        .request::<Secrets>("GET", &format!("/projects/{}/secrets", project.id), None)
+        .await?
+        .ok_or_else(|| anyhow!("Failed to parse response"))?
+        .secrets;
+
+    let missing = snapshot
+        .secrets
+        .iter()
+        .filter(|name| !secrets.iter().any(|secret| &secret.name == *name))
+        .map(|name| name.as_str())
+        .collect::<Vec<_>>();
+
+    if !missing.is_empty() {
+        log::warn!(
+            "Secrets {} are missing, set them with `hop secrets set`",
+            missing.join(", ")
+        );
+    }
+
+    if !new_webhooks.is_empty() {
+        log::warn!(
+            "Webhooks for {} have new secrets, update the endpoints that verify them",
+            new_webhooks.join(", ")
+        );
+    }
+
+    if !needs_image.is_empty() {
+        log::warn!(
+            "Deployments {} use images from {HOP_REGISTRY_URL}/{}, deploy them again with `hop deploy`",
+            needs_image.join(", "),
+            project.namespace
+        );
+    }
+
+    log::info!("Restored snapshot into project `{}`", project.namespace);
+
+    Ok(())
+}
@@ -0,0 +1,77 @@
+pub mod types;
+pub mod utils;
+
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use tokio::fs;
+
+use self::utils::take_snapshot;
+use crate::commands::projects::utils::format_project;
+use crate::state::State;
+
+#[derive(Debug, Parser)]
+#[clap(about = "Save the configuration of a project to a file")]
+#[group(skip)]
+pub struct Options {
+    #[clap(
+        short,
+        long,
+        help = "File to write the snapshot to, defaults to hop-snapshot_<namespace>_<date>.json"
+    )]
+    pub output: Option<PathBuf>,
+
+    #[clap(short, long, help = "Overwrite the file without asking")]
+    pub force: bool,
+}
+
+pub async fn handle(options: Options, state: State) -> Result<()> {
+    let project = state.ctx.current_project_error()?;
+
+    log::info!("Taking a snapshot of project {}", format_project(&project));
+
+    let output = options.output.unwrap_or_else(|| {
+        PathBuf::from(format!(
+            "hop-snapshot_{}_{}.json",
+            project.namespace,
+            chrono::Local::now().format("%Y-%m-%d_%H-%M-%S")
+        ))
+    });
+
+    if output.exists()
+        && !options.force
+        && !dialoguer::Confirm::new()
+            .with_prompt(format!(
+                "{} already exists, overwrite it?",
+                output.display()
+            ))
+            .default(false)
+            .interact_opt()?
+            .unwrap_or(false)
+    {
+        bail!("Aborted by user");
+    }
+
+    let snapshot = take_snapshot(&state, &project).await?;
+
+    fs::write(&output, serde_json::to_string_pretty(&snapshot)?)
+        .await
+        .with_context(|| format!("Could not write to {}", output.display()))?;
+
+    log::info!(
+        "Saved {} deployments, {} groups, {} webhooks, {} channels and {} secret names to {}",
+        snapshot.deployments.len(),
+        snapshot.groups.len(),
+        snapshot.webhooks.len(),
+        snapshot.channels.len(),
+        snapshot.secrets.len(),
+        output.display()
+    );
+
+    if !snapshot.secrets.is_empty() {
+        log::warn!("Secret values are not part of the snapshot, only their names");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,76 @@
+use hop::webhooks::types::PossibleEvents;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::commands::channels::types::ChannelType;
+use crate::commands::gateways::types::GatewayConfig;
+use crate::commands::ignite::health::types::CreateHealthCheck;
+use crate::commands::ignite::types::Config;
+
+/// Bump this whenever the snapshot format changes in a non additive way
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    pub created_at: String,
+    pub project: SnapshotProject,
+    #[serde(default)]
+    pub groups: Vec<SnapshotGroup>,
+    #[serde(default)]
+    pub deployments: Vec<SnapshotDeployment>,
+    #[serde(default)]
+    pub webhooks: Vec<SnapshotWebhook>,
+    #[serde(default)]
+    pub channels: Vec<SnapshotChannel>,
+    // only the names, values never leave the project
+    #[serde(default)]
+    pub secrets: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnapshotProject {
+    pub id: String,
+    pub name: String,
+    pub namespace: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnapshotGroup {
+    pub name: String,
+    pub position: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnapshotDeployment {
+    pub name: String,
+    pub config: Config,
+    pub target_container_count: u64,
+    pub group: Option<String>,
+    #[serde(default)]
+    pub gateways: Vec<SnapshotGateway>,
+    #[serde(default)]
+    pub health_checks: Vec<CreateHealthCheck>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnapshotGateway {
+    #[serde(flatten)]
+    pub config: GatewayConfig,
+    #[serde(default)]
+    pub domains: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnapshotWebhook {
+    pub url: String,
+    pub events: Vec<PossibleEvents>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnapshotChannel {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub type_: ChannelType,
+    pub state: Value,
+}
@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+
+use super::types::{
+    Snapshot, SnapshotChannel, SnapshotDeployment, SnapshotGateway, SnapshotGroup, SnapshotProject,
+    SnapshotWebhook, SNAPSHOT_VERSION,
+};
+use crate::commands::channels::utils::get_all_channels;
+use crate::commands::gateways::types::GatewayConfig;
+use crate::commands::gateways::util::get_all_gateways;
+use crate::commands::ignite::health::types::{CreateHealthCheck, HealthCheck};
+use crate::commands::ignite::health::utils::get_all_health_checks;
+use crate::commands::ignite::types::Deployment;
+use crate::commands::ignite::utils::get_all_deployments;
+use crate::commands::projects::types::Project;
+use crate::commands::secrets::types::Secrets;
+use crate::state::State;
+
+pub async fn take_snapshot(state: &State, project: &Project) -> Result<Snapshot> {
+    let secrets_path = format!("/projects/{}/secrets", project.id);
+
+    let (deployments, groups, webhooks, channels, secrets) = tokio::join!(
+        get_all_deployments(&state.http, &project.id),
+        state.hop.ignite.groups.get_all(&project.id),
+        state.hop.webhooks.get_all(&project.id),
+        get_all_channels(&state.http, &project.id),
+        state.http.request::<Secrets>("GET", &secrets_path, None),
+    );
+
+    let mut groups = groups?;
+    groups.sort_by_key(|group| group.position);
+
+    let group_names = groups
+        .iter()
+        .map(|group| (group.id.clone(), group.name.clone()))
+        .collect::<HashMap<_, _>>();
+
+    let mut snapshot_deployments = vec![];
+
+    for deployment in deployments? {
+        log::info!("Snapshotting deployment `{}`", deployment.name);
+
+        snapshot_deployments.push(snapshot_deployment(state, deployment, &group_names).await?);
+    }
+
+    Ok(Snapshot {
+        version: SNAPSHOT_VERSION,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        project: SnapshotProject {
+            id: project.id.clone(),
+            name: project.name.clone(),
+            namespace: project.namespace.clone(),
+        },
+        groups: groups
+            .into_iter()
+            .map(|group| SnapshotGroup {
+                name: group.name,
+                position: group.position,
+            })
+            .collect(),
+        deployments: snapshot_deployments,
+        webhooks: webhooks?
+            .into_iter()
+            .map(|webhook| SnapshotWebhook {
+                url: webhook.webhook_url,
+                events: webhook.events,
+            })
+            .collect(),
+        channels: channels?
+            .into_iter()
+            .map(|channel| SnapshotChannel {
+                id: channel.id,
+                type_: channel.type_,
+                state: channel.state,
+            })
+            .collect(),
+        secrets: secrets?
+            .ok_or_else(|| anyhow!("Failed to parse response"))?
+            .secrets
+            .into_iter()
+            .map(|secret| secret.name)
+            .collect(),
+    })
+}
+
+/// Deployment with its gateways and health checks, `group_names` maps group ids to names
+pub async fn snapshot_deployment(
+    state: &State,
+    deployment: Deployment,
+    group_names: &HashMap<String, String>,
+) -> Result<SnapshotDeployment> {
+    let (gateways, health_checks) = tokio::join!(
+        get_all_gateways(&state.http, &deployment.id),
+        get_all_health_checks(&state.http, &deployment.id)
+    );
+
+    let gateways = gateways?
+        .iter()
+        .map(|gateway| SnapshotGateway {
+            config: GatewayConfig::from_gateway(gateway),
+            domains: gateway
+                .domains
+                .iter()
+                .map(|domain| domain.domain.clone())
+                .collect(),
+        })
+        .collect();
+
+    let health_checks = health_checks?
+        .iter()
+        .map(health_check_to_config)
+        .collect::<Result<_>>()?;
+
+    Ok(SnapshotDeployment {
+        group: deployment
+            .group_id
+            .as_ref()
+            .and_then(|id| group_names.get(id).cloned()),
+        name: deployment.name,
+        config: deployment.config,
+        target_container_count: deployment.target_container_count,
+        gateways,
+        health_checks,
+    })
+}
+
+pub fn health_check_to_config(health_check: &HealthCheck) -> Result<CreateHealthCheck> {
+    Ok(CreateHealthCheck {
+        initial_delay: health_check.initial_delay,
+        interval: health_check.interval,
+        max_retries: health_check.max_retries,
+        path: health_check.path.clone(),
+        protocol: health_check.protocol.clone(),
+        port: health_check.port.try_into()?,
+        timeout: health_check.timeout,
+        success_threshold: health_check.success_threshold,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    fn health_check(port: u64) -> HealthCheck {
+        serde_json::from_value(json!({
+            "id": "health_check_1",
+            "deployment_id": "deployment_1",
+            "initial_delay": 10,
+            "interval": 30,
+            "max_retries": 5,
+            "path": "/health",
+            "protocol": "HTTP",
+            "port": port,
+            "timeout": 20,
+            "success_threshold": 2,
+            "created_at": "2023-01-01T00:00:00Z",
+            "type": "liveness",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_health_check_to_config() {
+        let config = health_check_to_config(&health_check(8080)).unwrap();
+
+        assert_eq!(config.initial_delay, 10);
+        assert_eq!(config.interval, 30);
+        assert_eq!(config.max_retries, 5);
+        assert_eq!(config.path, "/health");
+        assert_eq!(config.protocol, "HTTP");
+        assert_eq!(config.port, 8080);
+        assert_eq!(config.timeout, 20);
+        assert_eq!(config.success_threshold, 2);
+
+        // ports that don't fit a u16 can't be recreated
+        assert!(health_check_to_config(&health_check(70000)).is_err());
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let v1 = json!({
+            "version": 1,
+            "created_at": "2023-01-01T00:00:00Z",
+            "project": {
+                "id": "project_1",
+                "name": "Project",
+                "namespace": "project",
+            },
+            "groups": [{ "name": "backend", "position": 0 }],
+            "deployments": [{
+                "name": "api",
+                "config": {
+                    "type": "persistent",
+                    "image": { "name": "registry.hop.io/project/api:latest" },
+                    "env": { "DB_URL": "${secrets.DB_URL}" },
+                },
+                "target_container_count": 2,
+                "group": "backend",
+                "gateways": [{
+                    "type": "external",
+                    "protocol": "http",
+                    "name": "web",
+                    "target_port": 8080,
+                    "domains": ["api.example.com"],
+                }],
+                "health_checks": [{
+                    "initial_delay": 5,
+                    "interval": 60,
+                    "max_retries": 3,
+                    "path": "/",
+                    "protocol": "HTTP",
+                    "port": 8080,
+                    "timeout": 50,
+                    "success_threshold": 1,
+                }],
+            }],
+            "webhooks": [{
+                "url": "https://example.com/hook",
+                "events": ["ignite.deployment.created"],
+            }],
+            "channels": [{ "id": "lobby", "type": "public", "state": { "open": true } }],
+            "secrets": ["DB_URL"],
+        });
+
+        let snapshot: Snapshot = serde_json::from_value(v1).unwrap();
+
+        assert_eq!(snapshot.version, SNAPSHOT_VERSION);
+        assert_eq!(snapshot.groups[0].name, "backend");
+
+        let deployment = &snapshot.deployments[0];
+
+        assert_eq!(deployment.group.as_deref(), Some("backend"));
+        assert_eq!(deployment.target_container_count, 2);
+        assert_eq!(
+            deployment.config.env.get("DB_URL").map(String::as_str),
+            Some("${secrets.DB_URL}")
+        );
+        assert_eq!(deployment.gateways[0].config.name.as_deref(), Some("web"));
+        assert_eq!(deployment.gateways[0].domains, vec!["api.example.com"]);
+        assert_eq!(deployment.health_checks[0].port, 8080);
+        assert_eq!(snapshot.secrets, vec!["DB_URL"]);
+
+        let serialized = serde_json::to_value(&snapshot).unwrap();
+        let reparsed: Snapshot = serde_json::from_value(serialized.clone()).unwrap();
+
+        assert_eq!(serde_json::to_value(&reparsed).unwrap(), serialized);
+
+        // everything but the project is optional
+        let minimal: Snapshot = serde_json::from_value(json!({
+            "version": 1,
+            "created_at": "2023-01-01T00:00:00Z",
+            "project": { "id": "project_1", "name": "Project", "namespace": "project" },
+        }))
+        .unwrap();
+
+        assert!(minimal.deployments.is_empty());
+        assert!(minimal.secrets.is_empty());
+    }
+}
@@ -1,7 +1,7 @@
 mod delete;
 mod list;
 mod set;
-mod types;
+pub mod types;
 pub mod utils;
 
 use anyhow::Result;
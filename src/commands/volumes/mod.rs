@@ -1,11 +1,11 @@
 pub mod backup;
-mod copy;
+pub mod copy;
 mod delete;
 mod list;
 mod mkdir;
 mod r#move;
 mod types;
-mod utils;
+pub mod utils;
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
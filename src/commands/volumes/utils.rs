@@ -127,7 +127,7 @@ pub fn format_file(file: &File) -> Result<String> {
     Ok(res)
 }
 
-fn get_volume_from_deployment(deployment: &str) -> Result<String> {
+pub fn get_volume_from_deployment(deployment: &str) -> Result<String> {
     let tail = deployment
         .split('_')
         .nth(1)
@@ -78,7 +78,8 @@ pub async fn run() -> Result<()> {
         }
     };
 
-    if let Err(error) = handle_command(cli.commands, state).await {
+    // boxed so the futures of all the commands do not end up on the stack
+    if let Err(error) = Box::pin(handle_command(cli.commands, state)).await {
         log::error!("{error}");
         log::debug!("{error:#?}");
         std::process::exit(1);
@@ -141,6 +141,15 @@ impl State {
         self.token.clone()
     }
 
+    /// Who is making changes, project tokens have no user
+    pub fn actor(&self) -> String {
+        match (&self.token_type, &self.ctx.current) {
+            (Some(TokenType::Ptk), Some(current)) => format!("project token ({})", current.name),
+            (_, Some(current)) => current.email.clone(),
+            _ => "unknown".to_string(),
+        }
+    }
+
     pub async fn get_deployment_by_name_or_id(&self, name_or_id: &str) -> Result<Deployment> {
         // deployments cannot contain underscores so we can use this to determine if
         // it's an id
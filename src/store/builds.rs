@@ -0,0 +1,34 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tokio::fs::{self, File};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use super::utils::home_path;
+use super::Storable;
+use crate::impl_store;
+
+/// Content hashes of the last build uploaded for each deployment, used when the api has no hash of the build
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct BuildCache {
+    pub deployments: HashMap<String, CachedBuild>,
+    /// detached builds that were not known to succeed yet
+    #[serde(default)]
+    pub pending: HashMap<String, CachedBuild>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CachedBuild {
+    pub build_id: String,
+    pub content_hash: String,
+}
+
+impl Storable for BuildCache {
+    fn path() -> Result<PathBuf> {
+        home_path(".hop/builds.json")
+    }
+}
+
+impl_store!(BuildCache);
@@ -11,6 +11,7 @@ use crate::commands::auth::types::AuthorizedClient;
 use crate::commands::projects::types::Project;
 use crate::config::EXEC_NAME;
 use crate::impl_store;
+use crate::utils::engine::ContainerEngine;
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct Context {
@@ -18,6 +19,12 @@ pub struct Context {
     pub default_project: Option<String>,
     /// stored in the context store file
     pub default_user: Option<String>,
+    /// hopfile environment to deploy to when none is specified
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_environment: Option<String>,
+    /// container engine for local builds, detected when not set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub container_engine: Option<ContainerEngine>,
     /// api url override, only save if its not null
     #[serde(skip_serializing_if = "Option::is_none")]
     pub override_api_url: Option<String>,
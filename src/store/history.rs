@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use tokio::fs::{self, File};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use super::utils::home_path;
+use super::Storable;
+use crate::commands::ignite::types::Config;
+use crate::impl_store;
+
+// oldest changes are dropped after this many per deployment
+const MAX_CHANGES: usize = 200;
+const SALT_LENGTH: usize = 32;
+
+/// Changes made to deployments from this machine, the api does not keep them
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct DeploymentHistory {
+    pub deployments: HashMap<String, Vec<HistoryEntry>>,
+    /// mixed into the fingerprints of env values so they can't be looked up
+    #[serde(default)]
+    pub salt: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HistoryEntry {
+    pub at: DateTime<Utc>,
+    pub by: String,
+    pub change: Change,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Change {
+    /// snapshot of the config before it was updated
+    Config {
+        before: Box<Config>,
+    },
+    Scale {
+        from: u64,
+        to: u64,
+    },
+    /// canary rollouts replace the containers from the CLI, so they have no rollout ID
+    Rollout {
+        #[serde(default)]
+        rollout_id: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        canary: Option<u8>,
+    },
+    Promote {
+        build_id: String,
+    },
+}
+
+impl DeploymentHistory {
+    /// Salt of this machine's history, created on first use
+    pub fn salt(&mut self) -> &str {
+        if self.salt.is_empty() {
+            self.salt = rand::thread_rng()
+                .sample_iter(Alphanumeric)
+                .take(SALT_LENGTH)
+                .map(char::from)
+                .collect();
+        }
+
+        &self.salt
+    }
+
+    pub fn push(&mut self, deployment_id: &str, entry: HistoryEntry) {
+        let entries = self
+            .deployments
+            .entry(deployment_id.to_string())
+            .or_default();
+
+        entries.push(entry);
+
+        if entries.len() > MAX_CHANGES {
+            entries.drain(..entries.len() - MAX_CHANGES);
+        }
+    }
+}
+
+impl Storable for DeploymentHistory {
+    fn path() -> Result<PathBuf> {
+        home_path(".hop/history.json")
+    }
+}
+
+impl_store!(DeploymentHistory);
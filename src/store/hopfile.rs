@@ -1,3 +1,4 @@
+use std::collections::{BTreeMap, HashMap};
 use std::env::current_dir;
 use std::path::PathBuf;
 
@@ -20,6 +21,8 @@ pub static VALID_HOP_FILENAMES: &[&str] = &[
 pub struct HopFile {
     pub version: u8,
     pub config: HopFileConfig,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub environments: BTreeMap<String, HopFileEnvironment>,
     #[serde(skip)]
     pub path: PathBuf,
 }
@@ -30,6 +33,18 @@ pub struct HopFileConfig {
     pub deployment_id: String,
 }
 
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct HopFileEnvironment {
+    #[serde(flatten)]
+    pub config: HopFileConfig,
+    /// env variables that override the ones of the deployment
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub env: HashMap<String, String>,
+    /// deploying to production environments requires confirmation
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub production: bool,
+}
+
 impl HopFile {
     pub fn new(path: PathBuf, project: &str, deployment: &str) -> HopFile {
         HopFile {
@@ -38,10 +53,39 @@ impl HopFile {
                 project_id: project.to_string(),
                 deployment_id: deployment.to_string(),
             },
+            environments: BTreeMap::new(),
             path,
         }
     }
 
+    /// Picks the environment to deploy to, an explicitly requested environment
+    /// has to exist while the default one falls back to the top level config
+    pub fn environment(
+        &self,
+        name: Option<&str>,
+        default: Option<&str>,
+    ) -> Result<Option<(&String, &HopFileEnvironment)>> {
+        if let Some(name) = name {
+            return self
+                .environments
+                .get_key_value(name)
+                .map(Some)
+                .with_context(|| {
+                    format!(
+                        "Environment `{name}` not found in {}, available: {}",
+                        self.path.display(),
+                        self.environments
+                            .keys()
+                            .map(String::as_str)
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                });
+        }
+
+        Ok(default.and_then(|default| self.environments.get_key_value(default)))
+    }
+
     fn serialize(path: PathBuf, content: Self) -> Option<String> {
         match path.extension() {
             Some(ext) => match ext.to_str() {
@@ -6,10 +6,13 @@ use serde::de::DeserializeOwned;
 use serde::Serialize;
 
 pub mod auth;
+pub mod builds;
 pub mod context;
+pub mod history;
 pub mod hopfile;
 pub mod macros;
 pub mod utils;
+pub mod workspace;
 
 pub trait Storable<T: Serialize + DeserializeOwned + Default + Clone = Self> {
     fn path() -> Result<PathBuf>;
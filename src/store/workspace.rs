@@ -0,0 +1,94 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use crate::commands::ignite::from_compose::utils::DependsOn;
+
+pub static VALID_WORKSPACE_FILENAMES: &[&str] = &[
+    "hop.workspace.yml",
+    "hop.workspace.yaml",
+    "hop.workspace.json",
+];
+
+/// Root config of a repository that deploys multiple services
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Workspace {
+    pub version: u8,
+    pub project_id: String,
+    pub services: BTreeMap<String, WorkspaceService>,
+    #[serde(skip)]
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct WorkspaceService {
+    /// directory of the service, relative to the workspace file
+    pub path: PathBuf,
+    /// directory that gets uploaded to the builder, relative to the workspace file,
+    /// defaults to `path`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<PathBuf>,
+    pub deployment_id: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+}
+
+impl DependsOn for WorkspaceService {
+    fn depends_on(&self) -> Option<Vec<String>> {
+        if self.depends_on.is_empty() {
+            None
+        } else {
+            Some(self.depends_on.clone())
+        }
+    }
+}
+
+impl Workspace {
+    fn deserialize(path: PathBuf, content: &str) -> Option<Self> {
+        let workspace: Option<Self> = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => serde_json::from_str(content).ok(),
+            _ => serde_yaml::from_str(content).ok(),
+        };
+
+        workspace.map(|mut workspace| {
+            workspace.path = path;
+            workspace
+        })
+    }
+
+    // Find a workspace file in the current directory or any of its parents.
+    pub async fn find(mut path: PathBuf) -> Option<Self> {
+        loop {
+            for filename in VALID_WORKSPACE_FILENAMES {
+                let file_path = path.join(filename);
+
+                if file_path.exists() {
+                    let content = fs::read_to_string(file_path.clone()).await.ok()?;
+
+                    return Self::deserialize(file_path, &content);
+                }
+            }
+
+            if !path.pop() {
+                break;
+            }
+        }
+
+        None
+    }
+
+    pub fn root(&self) -> PathBuf {
+        self.path
+            .parent()
+            .map(|parent| parent.to_path_buf())
+            .unwrap_or_default()
+    }
+
+    /// Directory to upload when building the service
+    pub fn build_context(&self, service: &WorkspaceService) -> PathBuf {
+        self.root()
+            .join(service.context.as_ref().unwrap_or(&service.path))
+    }
+}
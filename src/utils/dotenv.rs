@@ -0,0 +1,242 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+
+/// Parses the content of a `.env` file
+///
+/// Supports `export` prefixes, inline comments, single quoted values that are
+/// kept as is, double quoted values with escape sequences, quoted values that
+/// span multiple lines and `${VAR}` references to keys defined earlier in the file.
+/// References to anything else, like `${secrets.NAME}`, are left untouched.
+pub fn parse(content: &str) -> Result<HashMap<String, String>> {
+    let mut env = HashMap::new();
+    let mut lines = content
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| (idx + 1, line));
+
+    while let Some((number, line)) = lines.next() {
+        let line = line.trim_start();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let line = line
+            .strip_prefix("export")
+            .filter(|rest| rest.starts_with([' ', '\t']))
+            .map_or(line, str::trim_start);
+
+        let Some((key, value)) = line.split_once('=') else {
+            bail!(
+                "line {number}: expected `KEY=VALUE`, found `{}`",
+                line.trim_end()
+            );
+        };
+
+        let key = key.trim_end();
+
+        if !is_valid_key(key) {
+            bail!("line {number}: `{key}` is not a valid variable name");
+        }
+
+        let trimmed = value.trim_start();
+
+        let value = match trimmed.chars().next() {
+            Some(quote @ ('\'' | '"')) => {
+                let mut raw = trimmed[1..].to_string();
+
+                // keep reading lines until the quote is closed
+                let end = loop {
+                    if let Some(end) = find_closing_quote(&raw, quote) {
+                        break end;
+                    }
+
+                    let Some((_, next)) = lines.next() else {
+                        bail!("line {number}: missing closing {quote} in the value of `{key}`");
+                    };
+
+                    raw.push('\n');
+                    raw.push_str(next);
+                };
+
+                let rest = raw[end + 1..].trim();
+
+                if !rest.is_empty() && !rest.starts_with('#') {
+                    bail!("line {number}: unexpected `{rest}` after the closing {quote}");
+                }
+
+                raw.truncate(end);
+
+                if quote == '\'' {
+                    raw
+                } else {
+                    expand(&raw, &env, true)
+                }
+            }
+
+            _ => expand(strip_inline_comment(value).trim(), &env, false),
+        };
+
+        env.insert(key.to_string(), value);
+    }
+
+    Ok(env)
+}
+
+fn is_valid_key(key: &str) -> bool {
+    let mut chars = key.chars();
+
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+}
+
+/// Names that can be referenced with `${VAR}`
+fn is_valid_reference(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Byte index of the quote closing the value, escaped quotes are skipped in double quotes
+fn find_closing_quote(value: &str, quote: char) -> Option<usize> {
+    let mut chars = value.char_indices();
+
+    while let Some((idx, c)) = chars.next() {
+        if c == quote {
+            return Some(idx);
+        }
+
+        if c == '\\' && quote == '"' {
+            chars.next();
+        }
+    }
+
+    None
+}
+
+/// Everything after a `#` that follows whitespace is a comment
+fn strip_inline_comment(value: &str) -> &str {
+    // the value follows the `=` so `KEY=#fff` is not a comment
+    let mut previous = '=';
+
+    for (idx, c) in value.char_indices() {
+        if c == '#' && previous.is_whitespace() {
+            return &value[..idx];
+        }
+
+        previous = c;
+    }
+
+    value
+}
+
+fn expand(value: &str, env: &HashMap<String, String>, escapes: bool) -> String {
+    let mut expanded = String::with_capacity(value.len());
+    let mut chars = value.char_indices();
+
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '\\' if escapes => match chars.next().map(|(_, c)| c) {
+                Some('n') => expanded.push('\n'),
+                Some('r') => expanded.push('\r'),
+                Some('t') => expanded.push('\t'),
+                Some(c @ ('"' | '\\' | '$')) => expanded.push(c),
+                Some(c) => {
+                    expanded.push('\\');
+                    expanded.push(c);
+                }
+                None => expanded.push('\\'),
+            },
+
+            '$' => {
+                let reference = value[idx + 1..]
+                    .strip_prefix('{')
+                    .and_then(|rest| rest.split_once('}'))
+                    .map(|(name, _)| name)
+                    .filter(|name| is_valid_reference(name));
+
+                match reference.and_then(|name| env.get(name).map(|value| (name, value))) {
+                    Some((name, value)) => {
+                        expanded.push_str(value);
+
+                        // skip over `{VAR}`
+                        chars.nth(name.len() + 1);
+                    }
+
+                    None => expanded.push(c),
+                }
+            }
+
+            _ => expanded.push(c),
+        }
+    }
+
+    expanded
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn get<'a>(env: &'a HashMap<String, String>, key: &str) -> Option<&'a str> {
+        env.get(key).map(String::as_str)
+    }
+
+    #[test]
+    fn test_parse() {
+        let env = parse(
+            r#"
+# comment
+export HOST=localhost
+PORT = 8080 # inline comment
+COLOR=#fff
+EMPTY=
+SINGLE='it ${HOST} \n'
+DOUBLE="say \"hi\"\tto \$HOME at ${HOST}:${PORT}"
+URL=http://${HOST}:${PORT}
+SECRET=${secrets.DB_URL}
+UNKNOWN=${MISSING}
+MULTI="first
+second"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(get(&env, "HOST"), Some("localhost"));
+        assert_eq!(get(&env, "PORT"), Some("8080"));
+        assert_eq!(get(&env, "COLOR"), Some("#fff"));
+        assert_eq!(get(&env, "EMPTY"), Some(""));
+        assert_eq!(get(&env, "SINGLE"), Some(r"it ${HOST} \n"));
+        assert_eq!(
+            get(&env, "DOUBLE"),
+            Some("say \"hi\"\tto $HOME at localhost:8080")
+        );
+        assert_eq!(get(&env, "URL"), Some("http://localhost:8080"));
+        assert_eq!(get(&env, "SECRET"), Some("${secrets.DB_URL}"));
+        assert_eq!(get(&env, "UNKNOWN"), Some("${MISSING}"));
+        assert_eq!(get(&env, "MULTI"), Some("first\nsecond"));
+        assert_eq!(env.len(), 10);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |content| parse(content).unwrap_err().to_string();
+
+        assert_eq!(
+            error("A=1\nnope"),
+            "line 2: expected `KEY=VALUE`, found `nope`"
+        );
+        assert_eq!(error("1A=1"), "line 1: `1A` is not a valid variable name");
+        assert_eq!(
+            error("A=1\nB=\"open\n\nC=2"),
+            "line 2: missing closing \" in the value of `B`"
+        );
+        assert_eq!(
+            error("A='x' y"),
+            "line 1: unexpected `y` after the closing '"
+        );
+    }
+}
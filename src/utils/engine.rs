@@ -0,0 +1,125 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+use super::in_path;
+use crate::store::context::Context;
+
+const ENGINE_OVERRIDE: &str = "HOP_CONTAINER_ENGINE";
+
+/// CLI used to build, push and run images locally
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ContainerEngine {
+    Docker,
+    Podman,
+    Nerdctl,
+}
+
+impl FromStr for ContainerEngine {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        serde_json::from_str(&format!("\"{}\"", s.to_lowercase())).map_err(|e| anyhow!(e))
+    }
+}
+
+impl Display for ContainerEngine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            serde_json::to_string(self).unwrap().replace('"', "")
+        )
+    }
+}
+
+impl ContainerEngine {
+    pub fn values() -> Vec<Self> {
+        vec![Self::Docker, Self::Podman, Self::Nerdctl]
+    }
+
+    /// Engine from `HOP_CONTAINER_ENGINE`, the saved preference or the first one installed
+    pub async fn from_context(ctx: &Context) -> Result<Self> {
+        let configured = match std::env::var(ENGINE_OVERRIDE) {
+            Ok(engine) => Some(engine.parse()?),
+            Err(_) => ctx.container_engine,
+        };
+
+        if let Some(engine) = configured {
+            if !in_path(engine.binary()).await {
+                bail!("`{}` is not installed", engine.binary());
+            }
+
+            return Ok(engine);
+        }
+
+        Self::detect()
+            .await
+            .ok_or_else(|| anyhow!("No container engine found, install docker, podman or nerdctl"))
+    }
+
+    /// First engine found in the path
+    pub async fn detect() -> Option<Self> {
+        for engine in Self::values() {
+            if in_path(engine.binary()).await {
+                return Some(engine);
+            }
+        }
+
+        None
+    }
+
+    pub fn binary(&self) -> &'static str {
+        match self {
+            Self::Docker => "docker",
+            Self::Podman => "podman",
+            Self::Nerdctl => "nerdctl",
+        }
+    }
+
+    pub fn command(&self) -> Command {
+        let mut command = Command::new(self.binary());
+
+        if *self == Self::Docker {
+            // allows us to build a lot more stuff
+            command
+                .env("DOCKER_BUILDKIT", "1")
+                .env("DOCKER_SCAN_SUGGEST", "false");
+        }
+
+        command
+    }
+
+    /// Podman has no buildkit progress output
+    pub fn supports_progress(&self) -> bool {
+        *self != Self::Podman
+    }
+
+    /// Nixpacks builds with docker by itself, other engines build the plan it generates
+    pub fn builds_nixpacks(&self) -> bool {
+        *self == Self::Docker
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_container_engine_from_str() {
+        assert_eq!(
+            "docker".parse::<ContainerEngine>().unwrap(),
+            ContainerEngine::Docker
+        );
+        assert_eq!(
+            "Podman".parse::<ContainerEngine>().unwrap(),
+            ContainerEngine::Podman
+        );
+        assert_eq!(ContainerEngine::Nerdctl.to_string(), "nerdctl");
+        assert!("buildah".parse::<ContainerEngine>().is_err());
+    }
+}
@@ -1,22 +1,32 @@
 pub mod arisu;
 pub mod browser;
 pub mod deser;
+pub mod dotenv;
+pub mod engine;
 pub mod size;
 pub mod sudo;
 
+use std::env::temp_dir;
 use std::error::Error;
 use std::path::PathBuf;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use anyhow::{anyhow, Result};
 use chrono::{DateTime, Utc};
 use console::style;
 use fern::colors::{Color, ColoredLevelConfig};
 use log::{Level, LevelFilter};
-use ms::{__to_string__, ms};
+use ms::{__to_ms__, __to_string__, ms};
 use serde::Serialize;
 use serde_json::Value;
 use tokio::fs;
+use tokio::process::Command;
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::config::DEFAULT_EDITOR;
+
+static CTRLC_SENDER: Mutex<Option<UnboundedSender<&'static str>>> = Mutex::new(None);
 
 pub fn set_hook() {
     // setup a panic hook to easily exit the program on panic
@@ -40,6 +50,22 @@ pub fn set_hook() {
     }));
 }
 
+/// Sends `CANCEL` to `tx` on ctrl-c, the handler can only be registered once
+/// per process so later calls swap the channel it sends to
+pub fn on_ctrlc(tx: UnboundedSender<&'static str>) -> Result<()> {
+    let mut sender = CTRLC_SENDER.lock().unwrap();
+
+    if sender.replace(tx).is_none() {
+        ctrlc::set_handler(|| {
+            if let Some(tx) = CTRLC_SENDER.lock().unwrap().as_ref() {
+                tx.send("CANCEL").ok();
+            }
+        })?;
+    }
+
+    Ok(())
+}
+
 pub fn clean_term() {
     let term = console::Term::stdout();
 
@@ -105,6 +131,13 @@ pub fn relative_time(date: DateTime<Utc>) -> String {
     ms!(milis.unsigned_abs(), true)
 }
 
+/// Parses human durations like `30s` or `5m`
+pub fn parse_duration(duration: &str) -> Result<Duration> {
+    let millis = ms!(duration).ok_or_else(|| anyhow!("Invalid duration: {duration}"))?;
+
+    Ok(Duration::from_millis(millis))
+}
+
 pub fn ask_question_iter<T>(prompt: &str, choices: &[T], override_default: Option<T>) -> Result<T>
 where
     T: PartialEq + Clone + Serialize + Default,
@@ -154,6 +187,31 @@ pub async fn in_path(program: &str) -> bool {
     false
 }
 
+/// Shows the content in `less` or the editor of the user, for output too long for the terminal
+pub async fn open_in_pager(name: &str, content: &str) -> Result<()> {
+    let temp = temp_dir().join(format!("hop_{name}.txt"));
+
+    fs::write(&temp, content).await?;
+
+    let editor = if in_path("less").await {
+        "less".to_string()
+    } else {
+        std::env::var("EDITOR")
+            .or_else(|_| std::env::var("VISUAL"))
+            .unwrap_or_else(|_| DEFAULT_EDITOR.to_string())
+    };
+
+    log::info!("Opening logs in `{editor}`");
+
+    if let Err(e) = Command::new(editor).arg(&temp).status().await {
+        log::warn!("Failed to open logs: {}", e);
+    }
+
+    fs::remove_file(&temp).await?;
+
+    Ok(())
+}
+
 pub fn urlify(s: &str) -> String {
     style(s).bold().underlined().to_string()
 }